@@ -0,0 +1,163 @@
+//! Times one simulation step of synthetic variable graphs, from evaluating the variables through
+//! to rebuilding the paths of the shapes bound to them.
+//!
+//! Run with `cargo bench`. Every graph is printed on its own line as
+//!
+//! ```text
+//! <graph> <variables> <median µs> <min µs> <max µs>
+//! ```
+//!
+//! so runs from before and after a change can be compared with `diff` or a spreadsheet.
+
+use std::time::{Duration, Instant};
+
+use bevy::prelude::*;
+use bevy_prototype_lyon::prelude::PathBuilder;
+
+use fourier::drawing::boundline::{update_bound_lines, BoundLine};
+use fourier::drawing::boundtracker::{update_bound_trackers, BoundTracker};
+use fourier::expr;
+use fourier::variables::binding::update_bindings;
+use fourier::variables::lambda::{Sum, Var};
+use fourier::variables::{devaluate_variables, evaluate_variables, Independent, Variable};
+
+const SIZES: [usize; 4] = [10, 100, 1_000, 10_000];
+/// How many points each tracker keeps. The trackers start full, as they are after a few seconds of running.
+const TRACKER_LENGTH: usize = 100;
+/// Roughly how long to spend timing each graph.
+const BUDGET: Duration = Duration::from_secs(2);
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, StageLabel)]
+struct StepStage;
+
+/// Each variable is one more than the last.
+fn chain(world: &mut World, size: usize) -> Vec<Entity> {
+    let mut prev = Var::independent("root").spawn(world);
+    let mut variables = vec![prev];
+    for _ in 1..size {
+        prev = Var::dependent("link", expr!(prev + 1.)).spawn(world);
+        variables.push(prev);
+    }
+    variables
+}
+
+/// A single variable that depends on every other one.
+fn fan_in(world: &mut World, size: usize) -> Vec<Entity> {
+    let mut variables: Vec<Entity> = (1..size)
+        .map(|_| Var::independent("leaf").spawn(world))
+        .collect();
+    let sum = Var::dependent("sum", Sum(variables.clone())).spawn(world);
+    variables.push(sum);
+    variables
+}
+
+/// Rows of sine waves, like the Fourier page, added together.
+fn fourier(world: &mut World, size: usize) -> Vec<Entity> {
+    let time = Var::independent("time").spawn(world);
+    let mut variables = vec![time];
+    let mut sines = Vec::new();
+    for row in 0..(size.saturating_sub(2) / 4).max(1) {
+        let freq = Var::independent("freq").value(row as f64 + 1.).spawn(world);
+        let amp = Var::independent("amp").value(10.).spawn(world);
+        let theta = Var::dependent("theta", expr!(time * freq)).spawn(world);
+        let sin_theta = Var::dependent("sin(theta)", expr!(amp * sin(theta))).spawn(world);
+        variables.extend([freq, amp, theta, sin_theta]);
+        sines.push(sin_theta);
+    }
+    variables.push(Var::dependent("sum", Sum(sines)).spawn(world));
+    variables
+}
+
+/// Bind a line and a tracker to every tenth variable.
+fn bind_shapes(world: &mut World, variables: &[Entity]) {
+    for pair in variables.chunks(10) {
+        let (first, last) = (pair[0], pair[pair.len() - 1]);
+        world
+            .spawn()
+            .insert(PathBuilder::new().build())
+            .insert(BoundLine::new(first, first, last, last));
+        let mut tracker = BoundTracker::new(last, TRACKER_LENGTH);
+        tracker.history = vec![0.; TRACKER_LENGTH].into();
+        world
+            .spawn()
+            .insert(PathBuilder::new().build())
+            .insert(tracker);
+    }
+}
+
+/// Sample every tracker, as [`sample_bound_trackers`] does on every step while the clock runs.
+///
+/// [`sample_bound_trackers`]: fourier::drawing::boundtracker::sample_bound_trackers
+fn sample_trackers(mut tracker_query: Query<&mut BoundTracker>) {
+    for mut tracker in tracker_query.iter_mut() {
+        tracker.sample();
+    }
+}
+
+/// Move every independent variable a little, so every step has something new to evaluate.
+fn nudge_variables(mut var_query: Query<&mut Variable, With<Independent>>) {
+    for mut var in var_query.iter_mut() {
+        let value = var.value();
+        var.set_value(value + 1. / 120.);
+    }
+}
+
+fn bench_app(build: fn(&mut World, usize) -> Vec<Entity>, size: usize) -> App {
+    let mut app = App::new();
+    app.add_plugins(MinimalPlugins).add_stage_after(
+        CoreStage::Update,
+        StepStage,
+        SystemStage::parallel()
+            .with_system(nudge_variables.before("devaluate"))
+            .with_system(devaluate_variables.label("devaluate"))
+            .with_system(evaluate_variables.label("evaluate").after("devaluate"))
+            .with_system(update_bindings::<BoundLine>.label("bind").after("evaluate"))
+            .with_system(
+                update_bindings::<BoundTracker>
+                    .label("bind")
+                    .after("evaluate"),
+            )
+            .with_system(sample_trackers.label("sample").after("bind"))
+            .with_system(update_bound_lines.after("bind"))
+            .with_system(update_bound_trackers.after("sample")),
+    );
+    let variables = build(&mut app.world, size);
+    bind_shapes(&mut app.world, &variables);
+    app.update();
+    app
+}
+
+fn bench(name: &str, build: fn(&mut World, usize) -> Vec<Entity>, size: usize) {
+    let mut app = bench_app(build, size);
+    let mut times = Vec::new();
+    let start = Instant::now();
+    while times.len() < 10 || (start.elapsed() < BUDGET && times.len() < 10_000) {
+        let step = Instant::now();
+        app.update();
+        times.push(step.elapsed());
+    }
+    times.sort();
+    let micros = |w: Duration| w.as_secs_f64() * 1e6;
+    println!(
+        "{:<8} {:>6} {:>12.1} {:>12.1} {:>12.1}",
+        name,
+        size,
+        micros(times[times.len() / 2]),
+        micros(times[0]),
+        micros(times[times.len() - 1])
+    );
+}
+
+fn main() {
+    println!(
+        "{:<8} {:>6} {:>12} {:>12} {:>12}",
+        "graph", "vars", "median_us", "min_us", "max_us"
+    );
+    let graphs: [(&str, fn(&mut World, usize) -> Vec<Entity>); 3] =
+        [("chain", chain), ("fan_in", fan_in), ("fourier", fourier)];
+    for (name, build) in graphs {
+        for size in SIZES {
+            bench(name, build, size);
+        }
+    }
+}
@@ -0,0 +1,221 @@
+//! Keyframe animation of independent variables, driven by the clock of the page they are on.
+
+use bevy::prelude::*;
+
+use crate::clock::{PageClocks, SimulationClock};
+use crate::variables::{Independent, SimulationStage, Variable};
+use crate::Page;
+
+/// Adds the system that moves animated variables along their keyframes.
+pub struct AnimationPlugin;
+
+impl Plugin for AnimationPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system_to_stage(
+            SimulationStage,
+            animate_variables
+                .label("animate")
+                .after("advance_page_clocks")
+                .before("variable_recalculation"),
+        );
+    }
+}
+
+/// How a value moves from one keyframe to the next.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Easing {
+    Linear,
+    EaseInOut,
+    /// Hold the previous value, then jump when the keyframe is reached.
+    Step,
+}
+
+impl Easing {
+    /// Map progress through a segment, from 0 to 1, onto how far the value has moved.
+    pub fn apply(self, t: f64) -> f64 {
+        match self {
+            Easing::Linear => t,
+            Easing::EaseInOut => t * t * (3. - 2. * t),
+            Easing::Step => {
+                if t < 1. {
+                    0.
+                } else {
+                    1.
+                }
+            }
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct Keyframe {
+    pub time: f64,
+    pub value: f64,
+    /// The easing used on the way into this keyframe.
+    pub easing: Easing,
+}
+
+/// What happens when an animation reaches its last keyframe.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Playback {
+    Once,
+    Loop,
+    PingPong,
+}
+
+/// How far an [`Animation`] has got, saved in every recorded frame so that scrubbing back puts
+/// it where it was.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct AnimationState {
+    elapsed: f64,
+    last_written: Option<f64>,
+    held_for: Option<f64>,
+    playing: bool,
+}
+
+/// Drives the independent [`Variable`] on the same entity through a list of keyframes.
+///
+/// If the entity has a [`Page`], the animation follows that page's clock, so it freezes or
+/// restarts along with the page. Otherwise it follows the simulation clock.
+///
+/// If anything else changes the variable, such as an inspector slider, the animation holds
+/// off until the variable has been left alone for `resume_after` seconds.
+#[derive(Component, Clone)]
+pub struct Animation {
+    keyframes: Vec<Keyframe>,
+    elapsed: f64,
+    last_written: Option<f64>,
+    held_for: Option<f64>,
+    pub playback: Playback,
+    pub speed: f64,
+    pub playing: bool,
+    pub resume_after: f64,
+}
+
+impl Animation {
+    pub fn new(playback: Playback) -> Self {
+        Self {
+            keyframes: Vec::new(),
+            elapsed: 0.,
+            last_written: None,
+            held_for: None,
+            playback,
+            speed: 1.,
+            playing: true,
+            resume_after: 1.,
+        }
+    }
+
+    /// Add a keyframe, keeping the keyframes ordered by time.
+    pub fn key(mut self, time: f64, value: f64, easing: Easing) -> Self {
+        let index = self.keyframes.partition_point(|w| w.time <= time);
+        self.keyframes.insert(
+            index,
+            Keyframe {
+                time,
+                value,
+                easing,
+            },
+        );
+        self
+    }
+
+    pub fn duration(&self) -> f64 {
+        self.keyframes.last().map_or(0., |w| w.time)
+    }
+
+    /// Jump back to the first keyframe.
+    pub fn restart(&mut self) {
+        self.elapsed = 0.;
+        self.playing = true;
+    }
+
+    pub fn state(&self) -> AnimationState {
+        AnimationState {
+            elapsed: self.elapsed,
+            last_written: self.last_written,
+            held_for: self.held_for,
+            playing: self.playing,
+        }
+    }
+
+    pub fn restore(&mut self, state: AnimationState) {
+        self.elapsed = state.elapsed;
+        self.last_written = state.last_written;
+        self.held_for = state.held_for;
+        self.playing = state.playing;
+    }
+
+    /// The animation time after applying the playback mode.
+    fn local_time(&self) -> f64 {
+        let duration = self.duration();
+        if duration <= 0. {
+            return 0.;
+        }
+        match self.playback {
+            Playback::Once => self.elapsed.min(duration),
+            Playback::Loop => self.elapsed.rem_euclid(duration),
+            Playback::PingPong => {
+                let t = self.elapsed.rem_euclid(2. * duration);
+                if t > duration {
+                    2. * duration - t
+                } else {
+                    t
+                }
+            }
+        }
+    }
+
+    /// The value of the animation at `time`.
+    pub fn sample(&self, time: f64) -> f64 {
+        let next = self.keyframes.partition_point(|w| w.time <= time);
+        match (
+            self.keyframes.get(next.wrapping_sub(1)),
+            self.keyframes.get(next),
+        ) {
+            (Some(from), Some(to)) => {
+                let t = (time - from.time) / (to.time - from.time);
+                from.value + (to.value - from.value) * to.easing.apply(t)
+            }
+            (Some(only), None) | (None, Some(only)) => only.value,
+            (None, None) => 0.,
+        }
+    }
+
+    pub fn value(&self) -> f64 {
+        self.sample(self.local_time())
+    }
+}
+
+fn animate_variables(
+    clock: Res<SimulationClock>,
+    clocks: Res<PageClocks>,
+    mut animation_query: Query<(&mut Animation, &mut Variable, Option<&Page>), With<Independent>>,
+) {
+    for (mut animation, mut var, page) in animation_query.iter_mut() {
+        let delta = page.map_or(clock.delta(), |w| clocks.get(*w).delta());
+        if animation.last_written.map_or(false, |w| w != var.value()) {
+            animation.held_for = Some(0.);
+        }
+        if let Some(held_for) = animation.held_for {
+            if held_for < animation.resume_after {
+                animation.held_for = Some(held_for + delta.abs());
+                animation.last_written = Some(var.value());
+                continue;
+            }
+            animation.held_for = None;
+        }
+        if !animation.playing {
+            animation.last_written = None;
+            continue;
+        }
+
+        animation.elapsed += delta * animation.speed;
+        if animation.playback == Playback::Once && animation.elapsed >= animation.duration() {
+            animation.playing = false;
+        }
+        let value = animation.value();
+        var.set_value(value);
+        animation.last_written = Some(value);
+    }
+}
@@ -0,0 +1,228 @@
+//! Named snapshots of the independent variables on a page, saved to disk and restored on request.
+
+use std::fs;
+
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContext};
+use serde::{Deserialize, Serialize};
+
+use crate::animation::Easing;
+use crate::history::SetFromOutside;
+use crate::variables::{group::Group, Independent, Variable};
+use crate::Page;
+
+/// Where bookmarks are kept between runs.
+const BOOKMARKS_PATH: &str = "bookmarks.json";
+
+/// Loads the saved [`Bookmarks`] and adds the bookmark panel. Its systems send [`SetFromOutside`],
+/// so they are labelled `"set_from_outside"` along with the history's.
+pub struct BookmarkPlugin;
+
+impl Plugin for BookmarkPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(Bookmarks::load())
+            .init_resource::<BookmarkPanel>()
+            .add_system(bookmark_panel.label("set_from_outside"))
+            .add_system(update_transitions.label("set_from_outside"));
+    }
+}
+
+/// Keeps a variable out of bookmarks, such as the answer the player of a game has to find.
+#[derive(Component)]
+pub struct Hidden;
+
+/// The value of one variable, identified by its group and name so it can be found again in a later run.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SnapshotValue {
+    pub group: usize,
+    pub name: String,
+    pub value: f64,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Snapshot {
+    pub name: String,
+    pub page: Page,
+    pub values: Vec<SnapshotValue>,
+}
+
+#[derive(Default, Serialize, Deserialize)]
+pub struct Bookmarks(pub Vec<Snapshot>);
+
+impl Bookmarks {
+    /// Read the bookmarks saved by an earlier run, starting empty if there are none.
+    pub fn load() -> Self {
+        let text = match fs::read_to_string(BOOKMARKS_PATH) {
+            Ok(text) => text,
+            Err(_) => return Self::default(),
+        };
+        serde_json::from_str(&text).unwrap_or_else(|e| {
+            warn!("Ignoring unreadable {}: {}", BOOKMARKS_PATH, e);
+            Self::default()
+        })
+    }
+
+    pub fn save(&self) {
+        let result = serde_json::to_string_pretty(self)
+            .map_err(|e| e.to_string())
+            .and_then(|text| fs::write(BOOKMARKS_PATH, text).map_err(|e| e.to_string()));
+        if let Err(e) = result {
+            warn!("Could not save {}: {}", BOOKMARKS_PATH, e);
+        }
+    }
+}
+
+/// Moves a variable from `from` to `to` over `duration` seconds of real time, so restoring a
+/// snapshot is visible even while the simulation clock is paused.
+#[derive(Component)]
+pub struct Transition {
+    from: f64,
+    to: f64,
+    duration: f64,
+    elapsed: f64,
+}
+
+struct BookmarkPanel {
+    name: String,
+    animate: bool,
+    duration: f64,
+}
+
+impl Default for BookmarkPanel {
+    fn default() -> Self {
+        Self {
+            name: String::new(),
+            animate: true,
+            duration: 1.,
+        }
+    }
+}
+
+/// Capture every independent variable on `page` that is not [`Hidden`].
+fn capture(
+    name: String,
+    page: Page,
+    var_query: &Query<
+        (Entity, &Name, &Group, &Page, &mut Variable),
+        (With<Independent>, Without<Hidden>),
+    >,
+) -> Snapshot {
+    let values = var_query
+        .iter()
+        .filter(|w| *w.3 == page)
+        .map(|(_, name, group, _, var)| SnapshotValue {
+            group: group.0,
+            name: name.to_string(),
+            value: var.value(),
+        })
+        .collect();
+    Snapshot { name, page, values }
+}
+
+fn bookmark_panel(
+    mut commands: Commands,
+    mut bookmarks: ResMut<Bookmarks>,
+    mut panel: ResMut<BookmarkPanel>,
+    mut egui_context: ResMut<EguiContext>,
+    page: Res<State<Page>>,
+    mut var_query: Query<
+        (Entity, &Name, &Group, &Page, &mut Variable),
+        (With<Independent>, Without<Hidden>),
+    >,
+    mut outside: EventWriter<SetFromOutside>,
+) {
+    let page = *page.current();
+    let mut restore = None;
+    let mut delete = None;
+    let mut add = false;
+    egui::Window::new("Bookmarks")
+        .anchor(egui::Align2::LEFT_BOTTOM, [10.0, -10.0])
+        .show(egui_context.ctx_mut(), |ui| {
+            ui.horizontal(|ui| {
+                ui.text_edit_singleline(&mut panel.name);
+                add = ui.button("Save").clicked();
+            });
+            ui.horizontal(|ui| {
+                ui.checkbox(&mut panel.animate, "Animate");
+                ui.add_enabled(
+                    panel.animate,
+                    egui::Slider::new(&mut panel.duration, 0.1..=5.).text("s"),
+                );
+            });
+            ui.separator();
+            for (index, snapshot) in bookmarks.0.iter().enumerate() {
+                if snapshot.page != page {
+                    continue;
+                }
+                ui.horizontal(|ui| {
+                    if ui.button(snapshot.name.as_str()).clicked() {
+                        restore = Some(index);
+                    }
+                    if ui.small_button("x").clicked() {
+                        delete = Some(index);
+                    }
+                });
+            }
+        });
+
+    if add {
+        let name = match panel.name.trim() {
+            "" => format!("{:?} {}", page, bookmarks.0.len() + 1),
+            name => name.to_string(),
+        };
+        bookmarks.0.push(capture(name, page, &var_query));
+        panel.name.clear();
+        bookmarks.save();
+    }
+    if let Some(index) = restore {
+        for saved in bookmarks.0[index].values.iter() {
+            let found = var_query
+                .iter_mut()
+                .find(|w| *w.3 == page && w.2 .0 == saved.group && w.1.as_str() == saved.name);
+            if let Some((entity, .., mut var)) = found {
+                if panel.animate {
+                    commands.entity(entity).insert(Transition {
+                        from: var.value(),
+                        to: saved.value,
+                        duration: panel.duration,
+                        elapsed: 0.,
+                    });
+                } else {
+                    commands.entity(entity).remove::<Transition>();
+                    var.set_value(saved.value);
+                    outside.send(SetFromOutside {
+                        variable: entity,
+                        value: saved.value,
+                    });
+                }
+            }
+        }
+    }
+    if let Some(index) = delete {
+        bookmarks.0.remove(index);
+        bookmarks.save();
+    }
+}
+
+/// Move variables along their transitions.
+fn update_transitions(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut transition_query: Query<(Entity, &mut Transition, &mut Variable)>,
+    mut outside: EventWriter<SetFromOutside>,
+) {
+    for (entity, mut transition, mut var) in transition_query.iter_mut() {
+        transition.elapsed += time.delta_seconds_f64();
+        let t = (transition.elapsed / transition.duration).min(1.);
+        let value =
+            transition.from + (transition.to - transition.from) * Easing::EaseInOut.apply(t);
+        var.set_value(value);
+        outside.send(SetFromOutside {
+            variable: entity,
+            value,
+        });
+        if t >= 1. {
+            commands.entity(entity).remove::<Transition>();
+        }
+    }
+}
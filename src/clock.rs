@@ -0,0 +1,299 @@
+//! The simulation clock, and the per-page clocks derived from it that page equations read from.
+
+use std::ops::RangeInclusive;
+
+use bevy::prelude::*;
+use bevy::time::FixedTimesteps;
+use bevy::utils::HashMap;
+use bevy_egui::{egui, EguiContext};
+use strum::IntoEnumIterator;
+
+use crate::variables::{group::Group, lambda::Var, SimulationStage, Variable, SIMULATION_TIMESTEP};
+use crate::{Headless, Page, Time, GLOBAL};
+
+/// Adds the [`SimulationClock`], a [`PageClock`] for every page and the systems that control them.
+pub struct ClockPlugin;
+
+impl Plugin for ClockPlugin {
+    fn build(&self, app: &mut App) {
+        let time = Var::independent("time")
+            .group(Group(GLOBAL))
+            .tag(Time)
+            .spawn(&mut app.world);
+        let page_clocks = Page::iter()
+            .map(|page| {
+                let variable = Var::independent(format!("time ({:?})", page))
+                    .group(Group(GLOBAL))
+                    .tag(Time)
+                    .spawn(&mut app.world);
+                (page, PageClock::new(variable))
+            })
+            .collect();
+        app.insert_resource(SimulationClock::new(time))
+            .insert_resource(PageClocks(page_clocks))
+            .add_system_to_stage(
+                SimulationStage,
+                advance_clock
+                    .label("advance_clock")
+                    .after("store_previous")
+                    .before("variable_recalculation"),
+            )
+            .add_system_to_stage(
+                SimulationStage,
+                advance_page_clocks
+                    .label("advance_page_clocks")
+                    .after("advance_clock")
+                    .before("variable_recalculation"),
+            );
+        if !app.world.contains_resource::<Headless>() {
+            app.add_system(clock_controls).add_system(clock_inspector);
+        }
+    }
+}
+
+/// The slowest and fastest the clock can be made to run, from the keyboard or the speed slider.
+const SPEEDS: RangeInclusive<f64> = 0.1..=4.;
+
+/// Simulated time, advanced by one fixed timestep scaled by `speed` per simulation step unless paused.
+pub struct SimulationClock {
+    variable: Entity,
+    elapsed: f64,
+    delta: f64,
+    pending: f64,
+    was_reset: bool,
+    pub speed: f64,
+    pub paused: bool,
+    /// How far a single step moves the clock, in simulated seconds.
+    pub step: f64,
+}
+
+impl SimulationClock {
+    pub fn new(variable: Entity) -> Self {
+        Self {
+            variable,
+            elapsed: 0.,
+            delta: 0.,
+            pending: 0.,
+            was_reset: false,
+            speed: 1.,
+            paused: false,
+            step: 1. / 60.,
+        }
+    }
+
+    /// The time variable that runs regardless of which page is showing.
+    pub fn variable(&self) -> Entity {
+        self.variable
+    }
+
+    pub fn elapsed(&self) -> f64 {
+        self.elapsed
+    }
+
+    /// How far the clock moved during the latest simulation step.
+    pub fn delta(&self) -> f64 {
+        self.delta
+    }
+
+    pub fn toggle_pause(&mut self) {
+        self.paused = !self.paused;
+    }
+
+    /// Pause the clock and move it forward by one step.
+    pub fn step_forward(&mut self) {
+        self.paused = true;
+        self.pending += self.step;
+    }
+
+    /// Pause the clock and move it back by one step.
+    pub fn step_back(&mut self) {
+        self.paused = true;
+        self.pending -= self.step;
+    }
+
+    /// Set this clock and every page clock back to zero.
+    pub fn reset(&mut self) {
+        self.was_reset = true;
+    }
+
+    /// Jump straight to `elapsed`, as when scrubbing back through a recording.
+    pub fn seek(&mut self, elapsed: f64) {
+        self.elapsed = elapsed;
+        self.pending = 0.;
+    }
+}
+
+/// What a page's clock does while the page is not showing.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EntryPolicy {
+    /// Freeze while hidden and carry on from the same moment when shown again.
+    Resume,
+    /// Freeze while hidden and start again from zero when shown again.
+    Restart,
+    /// Keep running while hidden.
+    Background,
+}
+
+/// The local time of a single page.
+pub struct PageClock {
+    variable: Entity,
+    elapsed: f64,
+    delta: f64,
+    pub policy: EntryPolicy,
+}
+
+impl PageClock {
+    fn new(variable: Entity) -> Self {
+        Self {
+            variable,
+            elapsed: 0.,
+            delta: 0.,
+            policy: EntryPolicy::Resume,
+        }
+    }
+
+    /// The time variable the page's equations should read from.
+    pub fn variable(&self) -> Entity {
+        self.variable
+    }
+
+    pub fn elapsed(&self) -> f64 {
+        self.elapsed
+    }
+
+    /// How far the clock moved during the latest simulation step. Zero while the page is hidden
+    /// and frozen, and on the step it was set back to zero.
+    pub fn delta(&self) -> f64 {
+        self.delta
+    }
+
+    pub fn seek(&mut self, elapsed: f64) {
+        self.elapsed = elapsed;
+    }
+}
+
+pub struct PageClocks(HashMap<Page, PageClock>);
+
+impl PageClocks {
+    pub fn get(&self, page: Page) -> &PageClock {
+        self.0.get(&page).unwrap()
+    }
+
+    pub fn get_mut(&mut self, page: Page) -> &mut PageClock {
+        self.0.get_mut(&page).unwrap()
+    }
+
+    /// The time variable the equations on `page` should read from.
+    pub fn variable(&self, page: Page) -> Entity {
+        self.get(page).variable
+    }
+}
+
+/// Move the clock forward by one simulation step and copy it into its time variable.
+fn advance_clock(
+    mut clock: ResMut<SimulationClock>,
+    timesteps: Res<FixedTimesteps>,
+    mut time_query: Query<&mut Variable, With<Time>>,
+) {
+    let mut delta = clock.pending;
+    if !clock.paused {
+        delta += timesteps.get(SIMULATION_TIMESTEP).unwrap().step() * clock.speed;
+    }
+    clock.pending = 0.;
+    clock.delta = delta;
+    clock.elapsed += delta;
+    if clock.was_reset {
+        clock.elapsed = 0.;
+    }
+    if let Ok(mut var) = time_query.get_mut(clock.variable) {
+        var.set_value(clock.elapsed);
+    }
+}
+
+/// Advance the clock of the current page, and of any page that runs in the background.
+fn advance_page_clocks(
+    mut clock: ResMut<SimulationClock>,
+    mut clocks: ResMut<PageClocks>,
+    page: Res<State<Page>>,
+    mut last_page: Local<Option<Page>>,
+    mut time_query: Query<&mut Variable, With<Time>>,
+) {
+    let was_reset = std::mem::take(&mut clock.was_reset);
+    let entered = last_page.replace(*page.current()) != Some(*page.current());
+    for (key, page_clock) in clocks.0.iter_mut() {
+        let is_current = key == page.current();
+        let restart = is_current && entered && page_clock.policy == EntryPolicy::Restart;
+        page_clock.delta = 0.;
+        if was_reset || restart {
+            page_clock.elapsed = 0.;
+        } else if is_current || page_clock.policy == EntryPolicy::Background {
+            page_clock.delta = clock.delta;
+            page_clock.elapsed += clock.delta;
+        }
+        if let Ok(mut var) = time_query.get_mut(page_clock.variable) {
+            var.set_value(page_clock.elapsed);
+        }
+    }
+}
+
+/// Space pauses, comma and period step, minus and equals change speed and R resets.
+fn clock_controls(mut clock: ResMut<SimulationClock>, input: Res<Input<KeyCode>>) {
+    if input.just_pressed(KeyCode::Space) {
+        clock.toggle_pause();
+    }
+    if input.just_pressed(KeyCode::Period) {
+        clock.step_forward();
+    }
+    if input.just_pressed(KeyCode::Comma) {
+        clock.step_back();
+    }
+    if input.just_pressed(KeyCode::Equals) {
+        clock.speed = (clock.speed * 2.).clamp(*SPEEDS.start(), *SPEEDS.end());
+    }
+    if input.just_pressed(KeyCode::Minus) {
+        clock.speed = (clock.speed / 2.).clamp(*SPEEDS.start(), *SPEEDS.end());
+    }
+    if input.just_pressed(KeyCode::R) {
+        clock.reset();
+    }
+}
+
+fn clock_inspector(
+    mut clock: ResMut<SimulationClock>,
+    mut clocks: ResMut<PageClocks>,
+    page: Res<State<Page>>,
+    mut egui_context: ResMut<EguiContext>,
+) {
+    let ctx = &mut egui_context.ctx_mut();
+    let page_clock = clocks.get_mut(*page.current());
+    egui::Window::new("Clock")
+        .anchor(egui::Align2::RIGHT_TOP, [-10.0, 100.0])
+        .show(ctx, |ui| {
+            ui.label(format!("t = {:.2}", page_clock.elapsed));
+            ui.horizontal(|ui| {
+                if ui.button("<").clicked() {
+                    clock.step_back();
+                }
+                let label = if clock.paused { "Play" } else { "Pause" };
+                if ui.button(label).clicked() {
+                    clock.toggle_pause();
+                }
+                if ui.button(">").clicked() {
+                    clock.step_forward();
+                }
+                if ui.button("Reset").clicked() {
+                    clock.reset();
+                }
+            });
+            ui.horizontal(|ui| {
+                ui.label("Speed");
+                ui.add(egui::Slider::new(&mut clock.speed, SPEEDS).logarithmic(true));
+            });
+            ui.horizontal(|ui| {
+                ui.label("When hidden");
+                ui.selectable_value(&mut page_clock.policy, EntryPolicy::Resume, "Pause");
+                ui.selectable_value(&mut page_clock.policy, EntryPolicy::Restart, "Restart");
+                ui.selectable_value(&mut page_clock.policy, EntryPolicy::Background, "Run");
+            });
+        });
+}
@@ -0,0 +1,343 @@
+//! Axes with ticks, an optional grid and numeric labels, for reading values off the shapes
+//! drawn over them.
+
+use std::f32::consts::PI;
+
+use bevy::prelude::*;
+use bevy::time::FixedTimesteps;
+use bevy_prototype_lyon::prelude::{DrawMode, GeometryBuilder, Path, PathBuilder, StrokeMode};
+
+use super::boundtext::text_matches;
+use super::boundtracker::BoundTracker;
+use crate::clock::{PageClocks, SimulationClock};
+use crate::variables::binding::Bound;
+use crate::variables::SIMULATION_TIMESTEP;
+use crate::Page;
+
+/// The most ticks drawn along one axis, however small the tick spacing is made.
+const MAX_TICKS: usize = 200;
+/// How far ticks stick out from their axis.
+const TICK_LENGTH: f32 = 5.;
+
+/// How the values at the ticks along one axis are written.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LabelFormat {
+    Hidden,
+    Decimal,
+    /// As multiples of π, such as π/2 or 3π, where the value is close to one.
+    Pi,
+}
+
+/// One end of one axis, which can be bound to a variable.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Extent {
+    XMin,
+    XMax,
+    YMin,
+    YMax,
+}
+
+/// A pair of axes through `origin`, spanning `x` and `y` in value units.
+///
+/// When attached to a [`BoundTracker`] the x axis shows simulated time instead, lined up with
+/// the tracker's samples so the ticks scroll along with the trace. The time is that of the
+/// tracker's page, if it is on one. The tracker and the axes
+/// should share a position, and the tracker should not be anchored.
+#[derive(Component, Clone)]
+pub struct Axes {
+    /// Where the value (0, 0) is drawn, or where the newest sample is drawn when attached to a
+    /// tracker.
+    pub origin: Vec2,
+    /// How far one unit along each axis is drawn.
+    pub scale: Vec2,
+    pub x: (f32, f32),
+    pub y: (f32, f32),
+    /// The distance between ticks along each axis, in value units.
+    pub ticks: Vec2,
+    /// The color of the grid lines through every tick, if there is a grid.
+    pub grid: Option<Color>,
+    pub x_labels: LabelFormat,
+    pub y_labels: LabelFormat,
+    pub text_style: TextStyle,
+    pub tracker: Option<Entity>,
+    bindings: Vec<(Extent, Entity)>,
+    values: Vec<f32>,
+}
+
+impl Axes {
+    pub fn new(x: (f32, f32), y: (f32, f32), ticks: Vec2) -> Self {
+        Self {
+            origin: Vec2::ZERO,
+            scale: Vec2::ONE,
+            x,
+            y,
+            ticks,
+            grid: None,
+            x_labels: LabelFormat::Hidden,
+            y_labels: LabelFormat::Hidden,
+            text_style: TextStyle::default(),
+            tracker: None,
+            bindings: Vec::new(),
+            values: Vec::new(),
+        }
+    }
+
+    pub fn origin(mut self, origin: Vec2) -> Self {
+        self.origin = origin;
+        self
+    }
+
+    pub fn scale(mut self, scale: Vec2) -> Self {
+        self.scale = scale;
+        self
+    }
+
+    pub fn grid(mut self, color: Color) -> Self {
+        self.grid = Some(color);
+        self
+    }
+
+    /// Write the value at every tick, in `style`.
+    pub fn labels(mut self, x: LabelFormat, y: LabelFormat, style: TextStyle) -> Self {
+        self.x_labels = x;
+        self.y_labels = y;
+        self.text_style = style;
+        self
+    }
+
+    /// Take `extent` from the value of `variable`.
+    pub fn bind(mut self, extent: Extent, variable: Entity) -> Self {
+        self.bindings.push((extent, variable));
+        self
+    }
+
+    /// Show the time of each of `tracker`'s samples along the x axis.
+    pub fn attach(mut self, tracker: Entity) -> Self {
+        self.tracker = Some(tracker);
+        self
+    }
+
+    /// The extents of both axes, with any bound ends replaced by their variables' values.
+    pub fn extents(&self) -> ((f32, f32), (f32, f32)) {
+        let (mut x, mut y) = (self.x, self.y);
+        for (&(extent, _), &value) in self.bindings.iter().zip(self.values.iter()) {
+            match extent {
+                Extent::XMin => x.0 = value,
+                Extent::XMax => x.1 = value,
+                Extent::YMin => y.0 = value,
+                Extent::YMax => y.1 = value,
+            }
+        }
+        (x, y)
+    }
+}
+
+/// The multiples of `spacing` between `min` and `max`.
+fn ticks((min, max): (f32, f32), spacing: f32) -> Vec<f32> {
+    if spacing <= 0. || !spacing.is_finite() {
+        return Vec::new();
+    }
+    let first = (min.min(max) / spacing).ceil() as i64;
+    let last = (min.max(max) / spacing).floor() as i64;
+    (first..=last)
+        .take(MAX_TICKS)
+        .map(|w| w as f32 * spacing)
+        .collect()
+}
+
+fn format_label(value: f32, spacing: f32, format: LabelFormat) -> String {
+    if format == LabelFormat::Pi {
+        let multiple = value / PI;
+        for denominator in 1..=12 {
+            let numerator = (multiple * denominator as f32).round();
+            if (multiple * denominator as f32 - numerator).abs() > 1e-3 {
+                continue;
+            }
+            let numerator = numerator as i64;
+            let top = match numerator {
+                0 => return "0".into(),
+                1 => "π".to_string(),
+                -1 => "-π".to_string(),
+                n => format!("{}π", n),
+            };
+            return match denominator {
+                1 => top,
+                d => format!("{}/{}", top, d),
+            };
+        }
+    }
+    let decimals = (-spacing.log10().floor()).max(0.) as usize;
+    format!("{:.*}", decimals, value)
+}
+
+/// The grid lines of an [`Axes`], drawn as a child of it.
+#[derive(Component)]
+pub struct AxesGrid;
+
+/// One of the labels of an [`Axes`], drawn as a child of it. Labels are reused from one frame
+/// to the next, and hidden when there are more than there are ticks.
+#[derive(Component)]
+pub struct AxesLabel;
+
+/// Redraw every set of axes, its grid and its labels.
+pub(crate) fn update_axes(
+    mut commands: Commands,
+    clock: Res<SimulationClock>,
+    clocks: Res<PageClocks>,
+    timesteps: Res<FixedTimesteps>,
+    tracker_query: Query<(&BoundTracker, Option<&Page>)>,
+    mut axes_query: Query<(
+        Entity,
+        &Axes,
+        &mut Path,
+        &Visibility,
+        Option<&Page>,
+        Option<&Children>,
+    )>,
+    mut grid_query: Query<(&mut Path, &mut Visibility), (With<AxesGrid>, Without<Axes>)>,
+    mut label_query: Query<
+        (&mut Text, &mut Transform, &mut Visibility),
+        (With<AxesLabel>, Without<Axes>),
+    >,
+) {
+    for (entity, axes, mut path, parent_visibility, page, children) in axes_query.iter_mut() {
+        let ((mut x, y), mut x_origin, mut x_scale) = (axes.extents(), 0., axes.scale.x);
+        if let Some((tracker, page)) = axes.tracker.and_then(|w| tracker_query.get(w).ok()) {
+            // The newest sample is now, and each one before it is a sample's worth of time earlier.
+            let step = timesteps
+                .get(SIMULATION_TIMESTEP)
+                .map_or(1. / 60., |w| w.step())
+                * clock.speed
+                * tracker.time_scale as f64;
+            let now = page.map_or(clock.elapsed(), |w| clocks.get(*w).elapsed()) as f32;
+            x = (now - tracker.max_length as f32 * step as f32, now);
+            x_origin = now;
+            x_scale = -tracker.spacing / step as f32 * tracker.direction.x.signum();
+        }
+        let to_position = |value: Vec2| {
+            axes.origin + Vec2::new((value.x - x_origin) * x_scale, value.y * axes.scale.y)
+        };
+        let x_ticks = ticks(x, axes.ticks.x);
+        let y_ticks = ticks(y, axes.ticks.y);
+        // The axes cross at zero, or at the nearest end when zero is out of range.
+        let cross = Vec2::new(
+            x_origin.clamp(x.0.min(x.1), x.0.max(x.1)),
+            0_f32.clamp(y.0.min(y.1), y.0.max(y.1)),
+        );
+
+        let mut path_builder = PathBuilder::new();
+        path_builder.move_to(to_position(Vec2::new(x.0, cross.y)));
+        path_builder.line_to(to_position(Vec2::new(x.1, cross.y)));
+        path_builder.move_to(to_position(Vec2::new(cross.x, y.0)));
+        path_builder.line_to(to_position(Vec2::new(cross.x, y.1)));
+        for &tick in x_ticks.iter() {
+            let at = to_position(Vec2::new(tick, cross.y));
+            path_builder.move_to(at - Vec2::Y * TICK_LENGTH);
+            path_builder.line_to(at + Vec2::Y * TICK_LENGTH);
+        }
+        for &tick in y_ticks.iter() {
+            let at = to_position(Vec2::new(cross.x, tick));
+            path_builder.move_to(at - Vec2::X * TICK_LENGTH);
+            path_builder.line_to(at + Vec2::X * TICK_LENGTH);
+        }
+        *path = path_builder.build();
+
+        let mut grid_path = PathBuilder::new();
+        if axes.grid.is_some() {
+            for &tick in x_ticks.iter() {
+                grid_path.move_to(to_position(Vec2::new(tick, y.0)));
+                grid_path.line_to(to_position(Vec2::new(tick, y.1)));
+            }
+            for &tick in y_ticks.iter() {
+                grid_path.move_to(to_position(Vec2::new(x.0, tick)));
+                grid_path.line_to(to_position(Vec2::new(x.1, tick)));
+            }
+        }
+        let grid_path = grid_path.build();
+
+        let mut labels = Vec::new();
+        if axes.x_labels != LabelFormat::Hidden {
+            labels.extend(x_ticks.iter().map(|&tick| {
+                (
+                    format_label(tick, axes.ticks.x, axes.x_labels),
+                    to_position(Vec2::new(tick, cross.y)) - Vec2::Y * TICK_LENGTH,
+                    TextAlignment::TOP_CENTER,
+                )
+            }));
+        }
+        if axes.y_labels != LabelFormat::Hidden {
+            labels.extend(y_ticks.iter().filter(|w| **w != 0.).map(|&tick| {
+                (
+                    format_label(tick, axes.ticks.y, axes.y_labels),
+                    to_position(Vec2::new(cross.x, tick)) - Vec2::X * TICK_LENGTH,
+                    TextAlignment::CENTER_RIGHT,
+                )
+            }));
+        }
+
+        let mut has_grid = false;
+        let mut labels = labels.into_iter();
+        for &child in children.iter().flat_map(|w| w.iter()) {
+            if let Ok((mut path, mut visibility)) = grid_query.get_mut(child) {
+                *path = grid_path.clone();
+                // Visibility is not inherited, so follow the axes as their page is hidden and shown.
+                visibility.is_visible = parent_visibility.is_visible;
+                has_grid = true;
+            } else if let Ok((mut text, mut transform, mut visibility)) = label_query.get_mut(child)
+            {
+                visibility.is_visible = match labels.next() {
+                    Some((value, position, alignment)) => {
+                        if !text_matches(&text, &value, &axes.text_style, alignment) {
+                            *text = Text::from_section(value, axes.text_style.clone())
+                                .with_alignment(alignment);
+                        }
+                        transform.translation = position.extend(0.);
+                        parent_visibility.is_visible
+                    }
+                    None => false,
+                };
+            }
+        }
+
+        let mut spawner = commands.entity(entity);
+        if let (Some(color), false) = (axes.grid, has_grid) {
+            let grid_mode = DrawMode::Stroke(StrokeMode::new(color, 1.));
+            spawner.with_children(|parent| {
+                let mut grid = parent.spawn_bundle(GeometryBuilder::build_as(
+                    &grid_path,
+                    grid_mode,
+                    Transform::from_xyz(0., 0., -0.1),
+                ));
+                grid.insert(AxesGrid).insert(parent_visibility.clone());
+                if let Some(page) = page {
+                    grid.insert(*page);
+                }
+            });
+        }
+        for (value, position, alignment) in labels {
+            spawner.with_children(|parent| {
+                let mut label = parent.spawn_bundle(Text2dBundle {
+                    text: Text::from_section(value, axes.text_style.clone())
+                        .with_alignment(alignment),
+                    transform: Transform::from_translation(position.extend(0.)),
+                    visibility: parent_visibility.clone(),
+                    ..default()
+                });
+                label.insert(AxesLabel);
+                if let Some(page) = page {
+                    label.insert(*page);
+                }
+            });
+        }
+    }
+}
+
+impl Bound for Axes {
+    fn get_bindings(&self) -> Vec<Entity> {
+        self.bindings.iter().map(|w| w.1).collect()
+    }
+
+    fn set_bindings(&mut self, bindings: Vec<f64>) {
+        self.values = bindings.into_iter().map(|w| w as f32).collect();
+    }
+}
@@ -0,0 +1,75 @@
+use bevy::prelude::*;
+use bevy_prototype_lyon::prelude::{Path, PathBuilder};
+
+use crate::variables::binding::Bound;
+
+/// Part of a circle, from `start` radians anticlockwise through `sweep` radians, for showing angles.
+#[derive(Component, Clone)]
+pub struct BoundArc {
+    x: Entity,
+    y: Entity,
+    radius: Entity,
+    start: Entity,
+    sweep: Entity,
+    center: Vec2,
+    radius_value: f32,
+    start_value: f32,
+    sweep_value: f32,
+    /// Draw lines from the center to both ends, like a slice of pie.
+    pub sector: bool,
+}
+
+impl BoundArc {
+    pub fn new(x: Entity, y: Entity, radius: Entity, start: Entity, sweep: Entity) -> Self {
+        Self {
+            x,
+            y,
+            radius,
+            start,
+            sweep,
+            center: Vec2::ZERO,
+            radius_value: 1.,
+            start_value: 0.,
+            sweep_value: 0.,
+            sector: false,
+        }
+    }
+}
+
+pub(crate) fn update_bound_arcs(mut arc_query: Query<(&BoundArc, &mut Path)>) {
+    for (arc, mut path) in arc_query.iter_mut() {
+        let start = arc.center + Vec2::from_angle(arc.start_value) * arc.radius_value;
+        let mut path_builder = PathBuilder::new();
+        if arc.sector {
+            path_builder.move_to(arc.center);
+            path_builder.line_to(start);
+        } else {
+            path_builder.move_to(start);
+        }
+        path_builder.arc(
+            arc.center,
+            Vec2::splat(arc.radius_value),
+            arc.sweep_value,
+            0.,
+        );
+        if arc.sector {
+            path_builder.close();
+        }
+        *path = path_builder.build();
+    }
+}
+
+impl Bound for BoundArc {
+    fn get_bindings(&self) -> Vec<Entity> {
+        vec![self.x, self.y, self.radius, self.start, self.sweep]
+    }
+
+    fn set_bindings(&mut self, mut bindings: Vec<f64>) {
+        self.sweep_value = bindings.pop().unwrap() as f32;
+        self.start_value = bindings.pop().unwrap() as f32;
+        self.radius_value = bindings.pop().unwrap() as f32;
+        let y = bindings.pop().unwrap() as f32;
+        let x = bindings.pop().unwrap() as f32;
+        self.center = Vec2::new(x, y);
+    }
+}
@@ -0,0 +1,68 @@
+use bevy::prelude::*;
+use bevy_prototype_lyon::prelude::{Path, PathBuilder};
+
+use crate::variables::binding::Bound;
+
+/// A line with an arrowhead at its tip, for drawing vectors such as phasors.
+#[derive(Component, Clone)]
+pub struct BoundArrow {
+    x1: Entity,
+    y1: Entity,
+    x2: Entity,
+    y2: Entity,
+    from: Vec2,
+    to: Vec2,
+    /// How far the sides of the head reach back from the tip.
+    pub head_length: f32,
+    /// The angle between the shaft and each side of the head, in radians.
+    pub head_angle: f32,
+}
+
+impl BoundArrow {
+    /// An arrow from (`x1`, `y1`) to its tip at (`x2`, `y2`).
+    pub fn new(x1: Entity, y1: Entity, x2: Entity, y2: Entity) -> Self {
+        Self {
+            x1,
+            y1,
+            x2,
+            y2,
+            from: Vec2::ZERO,
+            to: Vec2::ZERO,
+            head_length: 10.,
+            head_angle: 0.5,
+        }
+    }
+}
+
+pub(crate) fn update_bound_arrows(mut arrow_query: Query<(&BoundArrow, &mut Path)>) {
+    for (arrow, mut path) in arrow_query.iter_mut() {
+        let mut path_builder = PathBuilder::new();
+        path_builder.move_to(arrow.from);
+        path_builder.line_to(arrow.to);
+        let back = (arrow.from - arrow.to).normalize_or_zero() * arrow.head_length;
+        if back != Vec2::ZERO {
+            let (sin, cos) = arrow.head_angle.sin_cos();
+            let left = Vec2::new(back.x * cos - back.y * sin, back.x * sin + back.y * cos);
+            let right = Vec2::new(back.x * cos + back.y * sin, -back.x * sin + back.y * cos);
+            path_builder.move_to(arrow.to + left);
+            path_builder.line_to(arrow.to);
+            path_builder.line_to(arrow.to + right);
+        }
+        *path = path_builder.build();
+    }
+}
+
+impl Bound for BoundArrow {
+    fn get_bindings(&self) -> Vec<Entity> {
+        vec![self.x1, self.y1, self.x2, self.y2]
+    }
+
+    fn set_bindings(&mut self, mut bindings: Vec<f64>) {
+        let y2 = bindings.pop().unwrap() as f32;
+        let x2 = bindings.pop().unwrap() as f32;
+        let y1 = bindings.pop().unwrap() as f32;
+        let x1 = bindings.pop().unwrap() as f32;
+        self.from = Vec2::new(x1, y1);
+        self.to = Vec2::new(x2, y2);
+    }
+}
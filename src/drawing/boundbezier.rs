@@ -0,0 +1,70 @@
+use bevy::prelude::*;
+use bevy_prototype_lyon::prelude::{Path, PathBuilder};
+
+use crate::variables::binding::Bound;
+
+/// A quadratic or cubic Bézier curve with every point bound to a pair of variables.
+#[derive(Component, Clone)]
+pub struct BoundBezier {
+    points: Vec<(Entity, Entity)>,
+    values: Vec<Vec2>,
+}
+
+impl BoundBezier {
+    /// A curve from `from` to `to`, pulled towards `control`.
+    pub fn quadratic(
+        from: (Entity, Entity),
+        control: (Entity, Entity),
+        to: (Entity, Entity),
+    ) -> Self {
+        Self::new(vec![from, control, to])
+    }
+
+    /// A curve from `from` to `to`, leaving towards `control1` and arriving from `control2`.
+    pub fn cubic(
+        from: (Entity, Entity),
+        control1: (Entity, Entity),
+        control2: (Entity, Entity),
+        to: (Entity, Entity),
+    ) -> Self {
+        Self::new(vec![from, control1, control2, to])
+    }
+
+    fn new(points: Vec<(Entity, Entity)>) -> Self {
+        Self {
+            values: vec![Vec2::ZERO; points.len()],
+            points,
+        }
+    }
+}
+
+pub(crate) fn update_bound_beziers(mut bezier_query: Query<(&BoundBezier, &mut Path)>) {
+    for (bezier, mut path) in bezier_query.iter_mut() {
+        let mut path_builder = PathBuilder::new();
+        match bezier.values[..] {
+            [from, control, to] => {
+                path_builder.move_to(from);
+                path_builder.quadratic_bezier_to(control, to);
+            }
+            [from, control1, control2, to] => {
+                path_builder.move_to(from);
+                path_builder.cubic_bezier_to(control1, control2, to);
+            }
+            _ => {}
+        }
+        *path = path_builder.build();
+    }
+}
+
+impl Bound for BoundBezier {
+    fn get_bindings(&self) -> Vec<Entity> {
+        self.points.iter().flat_map(|(x, y)| [*x, *y]).collect()
+    }
+
+    fn set_bindings(&mut self, bindings: Vec<f64>) {
+        self.values = bindings
+            .chunks(2)
+            .map(|w| Vec2::new(w[0] as f32, w[1] as f32))
+            .collect();
+    }
+}
@@ -0,0 +1,140 @@
+use std::ops::RangeInclusive;
+use std::sync::Arc;
+
+use bevy::prelude::*;
+use bevy_prototype_lyon::prelude::{Path, PathBuilder};
+
+use crate::variables::binding::Bound;
+use crate::variables::lambda::{Context, Lam};
+
+/// How the samples of a [`BoundFunctionPlot`] are laid out.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PlotMode {
+    /// The sweep value along x and the expression's value along y.
+    Cartesian,
+    /// The sweep value as an angle in radians and the expression's value as the distance from the origin.
+    Polar,
+}
+
+/// The whole curve of an expression over a range of one of its variables, such as y = A·sin(fx + φ).
+///
+/// The expression is evaluated on a copy of the values it reads, with the sweep variable
+/// replaced by each sample in turn, so the live graph is never changed. The expression has to
+/// read the sweep variable directly: variables that depend on it keep their live values.
+#[derive(Component, Clone)]
+pub struct BoundFunctionPlot {
+    expression: Arc<dyn Lam>,
+    sweep: Entity,
+    inputs: Vec<Entity>,
+    domain: RangeInclusive<f64>,
+    samples: usize,
+    mode: PlotMode,
+    scale: Vec2,
+    context: Context,
+    dirty: bool,
+}
+
+impl BoundFunctionPlot {
+    pub fn new(
+        expression: Arc<dyn Lam>,
+        sweep: Entity,
+        domain: RangeInclusive<f64>,
+        samples: usize,
+    ) -> Self {
+        let mut inputs = expression.children();
+        inputs.retain(|w| *w != sweep);
+        inputs.sort();
+        inputs.dedup();
+        Self {
+            expression,
+            sweep,
+            inputs,
+            domain,
+            samples,
+            mode: PlotMode::Cartesian,
+            scale: Vec2::ONE,
+            context: Context::default(),
+            dirty: true,
+        }
+    }
+
+    pub fn polar(mut self) -> Self {
+        self.mode = PlotMode::Polar;
+        self
+    }
+
+    /// Multiply both co-ordinates of every point by `scale`.
+    pub fn scale(mut self, scale: Vec2) -> Self {
+        self.scale = scale;
+        self
+    }
+
+    pub fn set_domain(&mut self, domain: RangeInclusive<f64>) {
+        self.domain = domain;
+        self.dirty = true;
+    }
+
+    pub fn set_samples(&mut self, samples: usize) {
+        self.samples = samples;
+        self.dirty = true;
+    }
+
+    pub fn set_mode(&mut self, mode: PlotMode) {
+        self.mode = mode;
+        self.dirty = true;
+    }
+
+    /// The points of the curve for the current input values.
+    pub fn points(&self) -> Vec<Vec2> {
+        let mut context = self.context.clone();
+        let steps = self.samples.max(2) - 1;
+        let (start, end) = (*self.domain.start(), *self.domain.end());
+        (0..=steps)
+            .map(|i| {
+                let sweep = start + (end - start) * i as f64 / steps as f64;
+                context.insert(self.sweep, sweep);
+                let value = self.expression.get(&context);
+                let point = match self.mode {
+                    PlotMode::Cartesian => Vec2::new(sweep as f32, value as f32),
+                    PlotMode::Polar => Vec2::from_angle(sweep as f32) * value as f32,
+                };
+                point * self.scale
+            })
+            .collect()
+    }
+}
+
+/// Redraw each plot whose inputs have changed since it was last drawn.
+pub(crate) fn update_bound_function_plots(
+    mut plot_query: Query<(&mut BoundFunctionPlot, &mut Path)>,
+) {
+    for (mut plot, mut path) in plot_query.iter_mut() {
+        if !plot.dirty {
+            continue;
+        }
+        let mut path_builder = PathBuilder::new();
+        let mut points = plot.points().into_iter().filter(|w| w.is_finite());
+        if let Some(first) = points.next() {
+            path_builder.move_to(first);
+        }
+        for point in points {
+            path_builder.line_to(point);
+        }
+        *path = path_builder.build();
+        plot.dirty = false;
+    }
+}
+
+impl Bound for BoundFunctionPlot {
+    fn get_bindings(&self) -> Vec<Entity> {
+        self.inputs.clone()
+    }
+
+    fn set_bindings(&mut self, bindings: Vec<f64>) {
+        let context: Context = self.inputs.iter().copied().zip(bindings).collect();
+        if context != self.context {
+            self.context = context;
+            self.dirty = true;
+        }
+    }
+}
@@ -30,7 +30,7 @@ impl BoundLine {
     }
 }
 
-pub(crate) fn update_bound_lines(mut line_query: Query<(&mut BoundLine, &mut Path)>) {
+pub fn update_bound_lines(mut line_query: Query<(&mut BoundLine, &mut Path)>) {
     for (line, mut path) in line_query.iter_mut() {
         let mut path_builder = PathBuilder::new();
         path_builder.line_to(Vec2::new(line.x1_value, line.y1_value));
@@ -0,0 +1,61 @@
+use bevy::prelude::*;
+use bevy_prototype_lyon::prelude::{Path, PathBuilder};
+
+use crate::variables::binding::Bound;
+
+/// A shape through any number of vertices, each with its x and y bound to variables.
+#[derive(Component, Clone)]
+pub struct BoundPolygon {
+    vertices: Vec<(Entity, Entity)>,
+    values: Vec<Vec2>,
+    /// Whether to join the last vertex back to the first.
+    pub closed: bool,
+}
+
+impl BoundPolygon {
+    pub fn new(vertices: Vec<(Entity, Entity)>) -> Self {
+        Self {
+            values: vec![Vec2::ZERO; vertices.len()],
+            vertices,
+            closed: true,
+        }
+    }
+
+    /// Leave the shape open, as a line through the vertices.
+    pub fn open(vertices: Vec<(Entity, Entity)>) -> Self {
+        Self {
+            closed: false,
+            ..Self::new(vertices)
+        }
+    }
+}
+
+pub(crate) fn update_bound_polygons(mut polygon_query: Query<(&BoundPolygon, &mut Path)>) {
+    for (polygon, mut path) in polygon_query.iter_mut() {
+        let mut path_builder = PathBuilder::new();
+        let mut values = polygon.values.iter();
+        if let Some(first) = values.next() {
+            path_builder.move_to(*first);
+        }
+        for value in values {
+            path_builder.line_to(*value);
+        }
+        if polygon.closed {
+            path_builder.close();
+        }
+        *path = path_builder.build();
+    }
+}
+
+impl Bound for BoundPolygon {
+    fn get_bindings(&self) -> Vec<Entity> {
+        self.vertices.iter().flat_map(|(x, y)| [*x, *y]).collect()
+    }
+
+    fn set_bindings(&mut self, bindings: Vec<f64>) {
+        self.values = bindings
+            .chunks(2)
+            .map(|w| Vec2::new(w[0] as f32, w[1] as f32))
+            .collect();
+    }
+}
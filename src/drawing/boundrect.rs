@@ -0,0 +1,59 @@
+use bevy::prelude::*;
+use bevy_prototype_lyon::prelude::{Path, PathBuilder};
+
+use crate::variables::binding::Bound;
+
+/// An axis-aligned rectangle around a bound center, with a bound width and height.
+#[derive(Component, Clone)]
+pub struct BoundRect {
+    x: Entity,
+    y: Entity,
+    width: Entity,
+    height: Entity,
+    x_value: f32,
+    y_value: f32,
+    width_value: f32,
+    height_value: f32,
+}
+
+impl BoundRect {
+    pub fn new(x: Entity, y: Entity, width: Entity, height: Entity) -> Self {
+        Self {
+            x,
+            y,
+            width,
+            height,
+            x_value: 0.,
+            y_value: 0.,
+            width_value: 1.,
+            height_value: 1.,
+        }
+    }
+}
+
+pub(crate) fn update_bound_rects(mut rect_query: Query<(&BoundRect, &mut Path)>) {
+    for (rect, mut path) in rect_query.iter_mut() {
+        let center = Vec2::new(rect.x_value, rect.y_value);
+        let half = Vec2::new(rect.width_value, rect.height_value) / 2.;
+        let mut path_builder = PathBuilder::new();
+        path_builder.move_to(center - half);
+        path_builder.line_to(center + Vec2::new(half.x, -half.y));
+        path_builder.line_to(center + half);
+        path_builder.line_to(center + Vec2::new(-half.x, half.y));
+        path_builder.close();
+        *path = path_builder.build();
+    }
+}
+
+impl Bound for BoundRect {
+    fn get_bindings(&self) -> Vec<Entity> {
+        vec![self.x, self.y, self.width, self.height]
+    }
+
+    fn set_bindings(&mut self, mut bindings: Vec<f64>) {
+        self.height_value = bindings.pop().unwrap() as f32;
+        self.width_value = bindings.pop().unwrap() as f32;
+        self.y_value = bindings.pop().unwrap() as f32;
+        self.x_value = bindings.pop().unwrap() as f32;
+    }
+}
@@ -0,0 +1,147 @@
+//! Stroke and fill colors, opacity and stroke width bound to variable values.
+
+use std::f32::consts::TAU;
+
+use bevy::prelude::*;
+use bevy_prototype_lyon::prelude::{DrawMode, FillMode, StrokeMode};
+
+use crate::variables::binding::Bound;
+
+/// One part of a color.
+///
+/// Hue is read in radians, so it can be bound straight to a phase. Give that phase a
+/// [`Modulus`](crate::variables::variable::Modulus) so the hue does not sweep back round the
+/// whole color wheel as the phase wraps. The others are read from 0 to 1.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Channel {
+    Hue,
+    Saturation,
+    Lightness,
+    Red,
+    Green,
+    Blue,
+    Alpha,
+}
+
+impl Channel {
+    fn apply(self, mut color: Color, value: f32) -> Color {
+        let unit = value.clamp(0., 1.);
+        match self {
+            Channel::Hue | Channel::Saturation | Channel::Lightness => {
+                if let Color::Hsla {
+                    mut hue,
+                    mut saturation,
+                    mut lightness,
+                    alpha,
+                } = color.as_hsla()
+                {
+                    match self {
+                        Channel::Hue => hue = (value.rem_euclid(TAU) / TAU) * 360.,
+                        Channel::Saturation => saturation = unit,
+                        _ => lightness = unit,
+                    }
+                    Color::hsla(hue, saturation, lightness, alpha)
+                } else {
+                    color
+                }
+            }
+            Channel::Red => *color.set_r(unit),
+            Channel::Green => *color.set_g(unit),
+            Channel::Blue => *color.set_b(unit),
+            Channel::Alpha => *color.set_a(unit),
+        }
+    }
+}
+
+/// What a variable bound to a [`BoundStyle`] controls.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StyleBinding {
+    Stroke(Channel),
+    Fill(Channel),
+    /// The stroke width, in pixels.
+    Width,
+}
+
+/// Sets the [`DrawMode`] of a shape from base colors, with any channel overridden by a variable.
+#[derive(Component, Clone)]
+pub struct BoundStyle {
+    pub stroke: Option<Color>,
+    pub fill: Option<Color>,
+    pub width: f32,
+    bindings: Vec<(StyleBinding, Entity)>,
+    values: Vec<f32>,
+}
+
+impl BoundStyle {
+    /// An outline of the given color and width, with no fill.
+    pub fn stroke(color: Color, width: f32) -> Self {
+        Self {
+            stroke: Some(color),
+            fill: None,
+            width,
+            bindings: Vec::new(),
+            values: Vec::new(),
+        }
+    }
+
+    /// A filled shape with no outline.
+    pub fn filled(color: Color) -> Self {
+        Self {
+            fill: Some(color),
+            stroke: None,
+            ..Self::stroke(Color::NONE, 0.)
+        }
+    }
+
+    /// Fill the shape as well as outlining it.
+    pub fn fill(mut self, color: Color) -> Self {
+        self.fill = Some(color);
+        self
+    }
+
+    /// Take `binding` from the value of `variable`.
+    pub fn bind(mut self, binding: StyleBinding, variable: Entity) -> Self {
+        self.bindings.push((binding, variable));
+        self
+    }
+
+    pub fn draw_mode(&self) -> DrawMode {
+        let mut stroke = self.stroke;
+        let mut fill = self.fill;
+        let mut width = self.width;
+        for (&(binding, _), &value) in self.bindings.iter().zip(self.values.iter()) {
+            match binding {
+                StyleBinding::Stroke(channel) => stroke = stroke.map(|w| channel.apply(w, value)),
+                StyleBinding::Fill(channel) => fill = fill.map(|w| channel.apply(w, value)),
+                StyleBinding::Width => width = value.max(0.),
+            }
+        }
+        match (fill, stroke) {
+            (Some(fill), Some(stroke)) => DrawMode::Outlined {
+                fill_mode: FillMode::color(fill),
+                outline_mode: StrokeMode::new(stroke, width),
+            },
+            (Some(fill), None) => DrawMode::Fill(FillMode::color(fill)),
+            (None, stroke) => {
+                DrawMode::Stroke(StrokeMode::new(stroke.unwrap_or(Color::NONE), width))
+            }
+        }
+    }
+}
+
+/// Rebuild the draw mode of every styled shape from its bound values.
+pub(crate) fn update_bound_styles(mut style_query: Query<(&BoundStyle, &mut DrawMode)>) {
+    for (style, mut mode) in style_query.iter_mut() {
+        *mode = style.draw_mode();
+    }
+}
+
+impl Bound for BoundStyle {
+    fn get_bindings(&self) -> Vec<Entity> {
+        self.bindings.iter().map(|w| w.1).collect()
+    }
+
+    fn set_bindings(&mut self, bindings: Vec<f64>) {
+        self.values = bindings.into_iter().map(|w| w as f32).collect();
+    }
+}
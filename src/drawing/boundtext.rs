@@ -0,0 +1,160 @@
+//! Text in the world, next to the shapes it describes, filled in from variable values.
+
+use bevy::prelude::*;
+
+use super::boundlocation::BoundLocation;
+use crate::variables::binding::Bound;
+
+/// Text written from a template such as `"A = {amp:.1}"`, where each `{name}` or
+/// `{name:.precision}` is replaced by the value of the variable given that name with
+/// [`BoundText::var`]. `{{` and `}}` write literal braces.
+///
+/// Put it on an entity with a [`Text2dBundle`]. If the entity also has a [`BoundLocation`] the
+/// text is moved there, shifted by `offset`.
+#[derive(Component, Clone)]
+pub struct BoundText {
+    template: String,
+    names: Vec<(String, Entity)>,
+    values: Vec<f64>,
+    pub style: TextStyle,
+    alignment: TextAlignment,
+    pub offset: Vec2,
+}
+
+impl BoundText {
+    pub fn new(template: impl Into<String>) -> Self {
+        Self {
+            template: template.into(),
+            names: Vec::new(),
+            values: Vec::new(),
+            style: TextStyle {
+                font_size: 20.,
+                color: Color::WHITE,
+                ..default()
+            },
+            alignment: TextAlignment::CENTER_LEFT,
+            offset: Vec2::ZERO,
+        }
+    }
+
+    /// Fill `{name}` in the template with the value of `variable`.
+    pub fn var(mut self, name: impl Into<String>, variable: Entity) -> Self {
+        self.names.push((name.into(), variable));
+        self
+    }
+
+    pub fn font(mut self, font: Handle<Font>) -> Self {
+        self.style.font = font;
+        self
+    }
+
+    pub fn size(mut self, size: f32) -> Self {
+        self.style.font_size = size;
+        self
+    }
+
+    pub fn color(mut self, color: Color) -> Self {
+        self.style.color = color;
+        self
+    }
+
+    pub fn alignment(mut self, alignment: TextAlignment) -> Self {
+        self.alignment = alignment;
+        self
+    }
+
+    pub fn offset(mut self, offset: Vec2) -> Self {
+        self.offset = offset;
+        self
+    }
+
+    /// The template with every placeholder filled in. Placeholders naming no variable are left
+    /// as they are.
+    pub fn render(&self) -> String {
+        let mut out = String::with_capacity(self.template.len());
+        let mut rest = self.template.as_str();
+        while let Some(start) = rest.find(|w| w == '{' || w == '}') {
+            out.push_str(&rest[..start]);
+            rest = &rest[start..];
+            if rest.starts_with("{{") || rest.starts_with("}}") {
+                out.push_str(&rest[..1]);
+                rest = &rest[2..];
+                continue;
+            }
+            let end = match rest.find('}') {
+                Some(end) if rest.starts_with('{') => end,
+                _ => {
+                    out.push_str(&rest[..1]);
+                    rest = &rest[1..];
+                    continue;
+                }
+            };
+            let placeholder = &rest[1..end];
+            let (name, precision) = match placeholder.split_once(":.") {
+                Some((name, precision)) => (name, precision.parse::<usize>().ok()),
+                None => (placeholder, None),
+            };
+            let value = self
+                .names
+                .iter()
+                .position(|w| w.0 == name.trim())
+                .and_then(|w| self.values.get(w));
+            match (value, precision) {
+                (Some(value), Some(precision)) => out.push_str(&format!("{:.*}", precision, value)),
+                (Some(value), None) => out.push_str(&value.to_string()),
+                (None, _) => out.push_str(&rest[..=end]),
+            }
+            rest = &rest[end + 1..];
+        }
+        out.push_str(rest);
+        out
+    }
+}
+
+/// Whether `text` already reads `value` in `style` and `alignment`. Text is only replaced when
+/// this is false, since replacing it lays it out again.
+pub(crate) fn text_matches(
+    text: &Text,
+    value: &str,
+    style: &TextStyle,
+    alignment: TextAlignment,
+) -> bool {
+    text.sections.len() == 1
+        && text.sections[0].value == value
+        && text.sections[0].style.font == style.font
+        && text.sections[0].style.font_size == style.font_size
+        && text.sections[0].style.color == style.color
+        && text.alignment.vertical == alignment.vertical
+        && text.alignment.horizontal == alignment.horizontal
+}
+
+/// Write every bound text from its template and move it to its location.
+pub(crate) fn update_bound_texts(
+    mut text_query: Query<(
+        &BoundText,
+        &mut Text,
+        &mut Transform,
+        Option<&BoundLocation>,
+    )>,
+) {
+    for (bound, mut text, mut transform, location) in text_query.iter_mut() {
+        let value = bound.render();
+        if !text_matches(&text, &value, &bound.style, bound.alignment) {
+            *text = Text::from_section(value, bound.style.clone()).with_alignment(bound.alignment);
+        }
+        if let Some(location) = location {
+            let position = location.vec2() + bound.offset;
+            transform.translation = position.extend(transform.translation.z);
+        }
+    }
+}
+
+impl Bound for BoundText {
+    fn get_bindings(&self) -> Vec<Entity> {
+        self.names.iter().map(|w| w.1).collect()
+    }
+
+    fn set_bindings(&mut self, bindings: Vec<f64>) {
+        self.values = bindings;
+    }
+}
@@ -1,38 +1,163 @@
+use std::collections::VecDeque;
+
 use bevy::prelude::*;
 use bevy_prototype_lyon::prelude::{Path, PathBuilder};
 
+use super::boundlocation::BoundLocation;
+use crate::clock::{PageClocks, SimulationClock};
 use crate::variables::binding::Bound;
+use crate::Page;
 
 #[derive(Component, Clone)]
 pub struct BoundTracker {
     target: Entity,
     pub target_value: f32,
-    pub history: Vec<f32>,
+    /// The newest value first.
+    pub history: VecDeque<f32>,
     pub max_length: usize,
+    /// The distance between neighbouring values along the trace.
+    pub spacing: f32,
+    /// The way the trace grows from its start, as a unit vector. Values are drawn along its
+    /// left-hand perpendicular.
+    pub direction: Vec2,
+    /// How many simulation steps pass between samples. Larger values fit more time into the
+    /// same length.
+    pub time_scale: usize,
+    /// An entity with a [`BoundLocation`] that the newest value is drawn at.
+    pub anchor: Option<Entity>,
+    steps_since_sample: usize,
+    /// How many values have been pushed onto the history so far.
+    samples: u64,
+}
+
+/// Where a tracker is between samples, recorded every simulation step so that a recording can
+/// rebuild the trace as it was drawn.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TrackerState {
+    steps_since_sample: usize,
+    samples: u64,
+    newest: Option<f32>,
 }
+
 impl BoundTracker {
     pub fn new(target: Entity, max_length: usize) -> Self {
         Self {
             target,
-            history: Vec::new(),
+            history: VecDeque::with_capacity(max_length + 1),
             target_value: 1.,
             max_length,
+            spacing: 2.,
+            direction: Vec2::X,
+            time_scale: 1,
+            anchor: None,
+            steps_since_sample: 0,
+            samples: 0,
         }
     }
-}
 
-pub(crate) fn update_bound_trackers(mut tracker_query: Query<(&mut Path, &mut BoundTracker)>) {
-    for (mut line, mut tracker) in &mut tracker_query.iter_mut() {
-        let mut path_builder = PathBuilder::new();
+    pub fn spacing(mut self, spacing: f32) -> Self {
+        self.spacing = spacing;
+        self
+    }
 
-        let new_y = tracker.target_value;
-        tracker.history.insert(0, new_y);
-        if tracker.history.len() > tracker.max_length {
-            tracker.history.pop();
+    pub fn direction(mut self, direction: Vec2) -> Self {
+        self.direction = direction.normalize_or_zero();
+        self
+    }
+
+    pub fn time_scale(mut self, steps_per_sample: usize) -> Self {
+        self.time_scale = steps_per_sample.max(1);
+        self
+    }
+
+    /// Start the trace at the location of `anchor`, following it as it moves.
+    pub fn anchor(mut self, anchor: Entity) -> Self {
+        self.anchor = Some(anchor);
+        self
+    }
+
+    /// Count one simulation step, pushing the current value onto the history every `time_scale`
+    /// steps.
+    pub fn sample(&mut self) {
+        self.steps_since_sample += 1;
+        if self.steps_since_sample < self.time_scale {
+            return;
         }
+        self.steps_since_sample = 0;
+        self.history.push_front(self.target_value);
+        self.history.truncate(self.max_length);
+        self.samples += 1;
+    }
+
+    pub fn state(&self) -> TrackerState {
+        TrackerState {
+            steps_since_sample: self.steps_since_sample,
+            samples: self.samples,
+            newest: self.history.front().copied(),
+        }
+    }
+
+    /// Go back to the first of `states`, rebuilding the history from the values that were
+    /// sampled. `states` are the ones recorded on that step and each step before it, newest
+    /// first.
+    pub fn restore(&mut self, states: impl IntoIterator<Item = TrackerState>) {
+        let mut states = states.into_iter().peekable();
+        if let Some(state) = states.peek() {
+            self.steps_since_sample = state.steps_since_sample;
+            self.samples = state.samples;
+        }
+        // Each sample shows up as the newest value on every step until the next one is taken.
+        let mut last = None;
+        let max_length = self.max_length;
+        self.history = states
+            .filter(|w| last.replace(w.samples) != Some(w.samples))
+            .map_while(|w| w.newest)
+            .take(max_length)
+            .collect();
+    }
+
+    /// Where each value in the history is drawn, newest first.
+    pub fn points(&self, anchor: Option<Vec2>) -> impl Iterator<Item = Vec2> + '_ {
+        let normal = self.direction.perp();
+        let newest = self.history.front().copied().unwrap_or_default();
+        // With an anchor, shift the trace along the normal so the newest value lands on it.
+        let origin = anchor.map_or(Vec2::ZERO, |w| w - normal * newest);
+        self.history.iter().enumerate().map(move |(index, value)| {
+            origin + self.direction * index as f32 * self.spacing + normal * *value
+        })
+    }
+}
+
+/// Record the current value of every tracker's variable. Runs once per simulation step.
+///
+/// A tracker on a [`Page`] only samples while that page's clock moves forward, so it does not
+/// fill up with a flat line while the page is hidden or paused. Trackers on no page follow the
+/// simulation clock.
+pub fn sample_bound_trackers(
+    clock: Res<SimulationClock>,
+    clocks: Res<PageClocks>,
+    mut tracker_query: Query<(&mut BoundTracker, Option<&Page>)>,
+) {
+    for (mut tracker, page) in tracker_query.iter_mut() {
+        if page.map_or(clock.delta(), |w| clocks.get(*w).delta()) > 0. {
+            tracker.sample();
+        }
+    }
+}
+
+pub fn update_bound_trackers(
+    mut tracker_query: Query<(&mut Path, &BoundTracker)>,
+    location_query: Query<&BoundLocation>,
+) {
+    for (mut line, tracker) in &mut tracker_query.iter_mut() {
+        let anchor = tracker
+            .anchor
+            .and_then(|w| location_query.get(w).ok())
+            .map(|w| w.vec2());
+        let mut path_builder = PathBuilder::new();
 
-        for (index, vertex) in tracker.history.iter().enumerate() {
-            path_builder.line_to(Vec2::new(index as f32 * 2., *vertex as f32));
+        for vertex in tracker.points(anchor) {
+            path_builder.line_to(vertex);
         }
         let new_path = path_builder.build();
         *line = new_path;
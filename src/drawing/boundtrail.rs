@@ -0,0 +1,238 @@
+use std::collections::VecDeque;
+
+use bevy::prelude::*;
+use bevy_prototype_lyon::prelude::{DrawMode, GeometryBuilder, Path, PathBuilder};
+
+use crate::clock::{PageClocks, SimulationClock};
+use crate::variables::binding::Bound;
+use crate::Page;
+
+/// The path traced by a point whose x and y are bound to variables, such as the tip of a chain
+/// of phasors in an epicycle drawing.
+///
+/// A trail on a [`Page`] follows that page's clock, and starts again whenever the page is shown.
+#[derive(Component, Clone)]
+pub struct BoundTrail {
+    x: Entity,
+    y: Entity,
+    value: Vec2,
+    /// The newest point first, each with the simulated time it was taken at.
+    history: VecDeque<(f64, Vec2)>,
+    pub max_length: usize,
+    /// How many bands the trail is split into, each fainter than the one before. 0 or 1 draws
+    /// the whole trail at full strength.
+    pub fade: usize,
+    /// Once the trail covers this many simulated seconds, draw it as a closed loop and stop it
+    /// growing. `max_length` has to hold at least a period's worth of steps for this to happen.
+    pub period: Option<f64>,
+}
+
+impl BoundTrail {
+    pub fn new(x: Entity, y: Entity, max_length: usize) -> Self {
+        Self {
+            x,
+            y,
+            value: Vec2::ZERO,
+            history: VecDeque::new(),
+            max_length,
+            fade: 0,
+            period: None,
+        }
+    }
+
+    pub fn faded(mut self, bands: usize) -> Self {
+        self.fade = bands;
+        self
+    }
+
+    pub fn looped(mut self, period: f64) -> Self {
+        self.period = Some(period);
+        self
+    }
+
+    pub fn history(&self) -> &VecDeque<(f64, Vec2)> {
+        &self.history
+    }
+
+    pub fn clear(&mut self) {
+        self.history.clear();
+    }
+
+    /// Whether the trail has covered a whole period. A trail whose point has not moved is never
+    /// closed, as there is no loop to draw.
+    pub fn is_closed(&self) -> bool {
+        match (self.period, self.history.front(), self.history.back()) {
+            (Some(period), Some(newest), Some(oldest)) => {
+                newest.0 - oldest.0 >= period && self.history.iter().any(|w| w.1 != newest.1)
+            }
+            _ => false,
+        }
+    }
+}
+
+/// One of the fainter bands of a fading [`BoundTrail`], drawn as a child of the trail.
+#[derive(Component)]
+pub struct TrailSegment(usize);
+
+/// Record where every trail's point is. Runs once per simulation step.
+pub(crate) fn sample_bound_trails(
+    clock: Res<SimulationClock>,
+    clocks: Res<PageClocks>,
+    mut trail_query: Query<(&mut BoundTrail, Option<&Page>)>,
+) {
+    for (mut trail, page) in trail_query.iter_mut() {
+        let (now, delta) = match page {
+            Some(page) => (clocks.get(*page).elapsed(), clocks.get(*page).delta()),
+            None => (clock.elapsed(), clock.delta()),
+        };
+        // Forget anything from after now, such as after stepping the clock back.
+        while trail.history.front().map_or(false, |w| w.0 > now) {
+            trail.history.pop_front();
+        }
+        if delta == 0. || trail.is_closed() {
+            continue;
+        }
+        let value = trail.value;
+        trail.history.push_front((now, value));
+        trail.history.truncate(trail.max_length);
+    }
+}
+
+/// Empty the trails on a page as it is shown, so they do not join up with where their points
+/// were when the page was left.
+pub(crate) fn clear_trails_on_enter(
+    page: Res<State<Page>>,
+    mut trail_query: Query<(&mut BoundTrail, &Page)>,
+) {
+    if !page.is_changed() {
+        return;
+    }
+    for (mut trail, trail_page) in trail_query.iter_mut() {
+        if trail_page == page.current() {
+            trail.clear();
+        }
+    }
+}
+
+fn build_path(points: impl Iterator<Item = Vec2>, closed: bool) -> Path {
+    let mut path_builder = PathBuilder::new();
+    let mut points = points;
+    if let Some(first) = points.next() {
+        path_builder.move_to(first);
+    }
+    for point in points {
+        path_builder.line_to(point);
+    }
+    if closed {
+        path_builder.close();
+    }
+    path_builder.build()
+}
+
+fn fade_draw_mode(mode: &DrawMode, strength: f32) -> DrawMode {
+    let fade = |mut color: Color| *color.set_a(color.a() * strength);
+    match *mode {
+        DrawMode::Fill(mut fill) => {
+            fill.color = fade(fill.color);
+            DrawMode::Fill(fill)
+        }
+        DrawMode::Stroke(mut stroke) => {
+            stroke.color = fade(stroke.color);
+            DrawMode::Stroke(stroke)
+        }
+        DrawMode::Outlined {
+            mut fill_mode,
+            mut outline_mode,
+        } => {
+            fill_mode.color = fade(fill_mode.color);
+            outline_mode.color = fade(outline_mode.color);
+            DrawMode::Outlined {
+                fill_mode,
+                outline_mode,
+            }
+        }
+    }
+}
+
+/// Draw the newest band of every trail on the trail itself and the older bands on its segments,
+/// spawning any segments that are missing.
+pub(crate) fn update_bound_trails(
+    mut commands: Commands,
+    mut trail_query: Query<(
+        Entity,
+        &BoundTrail,
+        &mut Path,
+        &DrawMode,
+        &Visibility,
+        Option<&Page>,
+        Option<&Children>,
+    )>,
+    mut segment_query: Query<
+        (&TrailSegment, &mut Path, &mut DrawMode, &mut Visibility),
+        Without<BoundTrail>,
+    >,
+) {
+    for (entity, trail, mut path, mode, visibility, page, children) in trail_query.iter_mut() {
+        let closed = trail.is_closed();
+        let bands = if closed { 1 } else { trail.fade.max(1) };
+        let band_length = (trail.history.len() + bands - 1) / bands;
+        // Each band starts at the last point of the band before, so there are no gaps.
+        let band = |index: usize| {
+            trail
+                .history
+                .iter()
+                .skip((index * band_length).saturating_sub(1))
+                .take(band_length + (index > 0) as usize)
+                .map(|w| w.1)
+        };
+
+        *path = build_path(band(0), closed);
+
+        let mut drawn = vec![false; trail.fade.max(1)];
+        drawn[0] = true;
+        for &child in children.iter().flat_map(|w| w.iter()) {
+            if let Ok((segment, mut segment_path, mut segment_mode, mut segment_visibility)) =
+                segment_query.get_mut(child)
+            {
+                // Visibility is not inherited, so follow the trail as its page is hidden and shown.
+                segment_visibility.is_visible = visibility.is_visible;
+                if segment.0 < bands {
+                    *segment_path = build_path(band(segment.0), false);
+                    *segment_mode = fade_draw_mode(mode, 1. - segment.0 as f32 / bands as f32);
+                } else {
+                    *segment_path = PathBuilder::new().build();
+                }
+                if let Some(drawn) = drawn.get_mut(segment.0) {
+                    *drawn = true;
+                }
+            }
+        }
+        for (index, _) in drawn.iter().enumerate().filter(|w| !*w.1) {
+            commands.entity(entity).with_children(|parent| {
+                let mut segment = parent.spawn_bundle(GeometryBuilder::build_as(
+                    &PathBuilder::new().build(),
+                    fade_draw_mode(mode, 1. - index as f32 / bands as f32),
+                    Transform::default(),
+                ));
+                segment
+                    .insert(TrailSegment(index))
+                    .insert(visibility.clone());
+                if let Some(page) = page {
+                    segment.insert(*page);
+                }
+            });
+        }
+    }
+}
+
+impl Bound for BoundTrail {
+    fn get_bindings(&self) -> Vec<Entity> {
+        vec![self.x, self.y]
+    }
+
+    fn set_bindings(&mut self, mut bindings: Vec<f64>) {
+        let y = bindings.pop().unwrap() as f32;
+        let x = bindings.pop().unwrap() as f32;
+        self.value = Vec2::new(x, y);
+    }
+}
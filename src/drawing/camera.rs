@@ -0,0 +1,176 @@
+//! The camera, the world-space frame each page is laid out in, and mouse controls to pan and
+//! zoom around it.
+
+use bevy::input::mouse::{MouseMotion, MouseScrollUnit, MouseWheel};
+use bevy::prelude::*;
+use bevy::render::camera::ScalingMode;
+use bevy::utils::HashMap;
+use bevy_egui::EguiContext;
+use strum::IntoEnumIterator;
+
+use crate::Page;
+
+/// How far one line of the mouse wheel zooms.
+const ZOOM_PER_LINE: f32 = 0.9;
+/// How many pixels of a touchpad scroll count as one line of the mouse wheel.
+const PIXELS_PER_LINE: f32 = 100.;
+
+/// Marks the camera that looks at the pages.
+#[derive(Component)]
+pub struct PageCamera;
+
+/// The part of the world a page is laid out in. However the window is shaped, all of it is
+/// shown, as large as fits, until the view is panned or zoomed.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PageFrame {
+    pub center: Vec2,
+    pub size: Vec2,
+}
+
+impl PageFrame {
+    pub fn new(center: Vec2, size: Vec2) -> Self {
+        Self { center, size }
+    }
+
+    /// A frame from (`left`, `bottom`) to (`right`, `top`), such as -1 to 1 in both directions.
+    pub fn from_corners(left: f32, bottom: f32, right: f32, top: f32) -> Self {
+        let (min, max) = (Vec2::new(left, bottom), Vec2::new(right, top));
+        Self::new((min + max) / 2., max - min)
+    }
+}
+
+impl Default for PageFrame {
+    /// The pages are laid out in pixels of the default window, with the origin in the middle.
+    fn default() -> Self {
+        Self::new(Vec2::ZERO, Vec2::new(1280., 720.))
+    }
+}
+
+/// The frame of every page.
+///
+/// The pages are laid out in pixels of the default window on purpose: their positions, radii,
+/// stroke widths and font sizes are all in pixels, so they keep their look while the frame
+/// scales them to fit any window. A page that is easier to describe in its own units, such as
+/// -1 to 1, can call [`PageFrames::set`] from its setup with a frame in those units.
+pub struct PageFrames(HashMap<Page, PageFrame>);
+
+impl Default for PageFrames {
+    fn default() -> Self {
+        Self(Page::iter().map(|w| (w, PageFrame::default())).collect())
+    }
+}
+
+impl PageFrames {
+    pub fn get(&self, page: Page) -> PageFrame {
+        self.0.get(&page).copied().unwrap_or_default()
+    }
+
+    /// Lay `page` out in `frame`. Takes effect straight away if `page` is showing.
+    pub fn set(&mut self, page: Page, frame: PageFrame) {
+        self.0.insert(page, frame);
+    }
+}
+
+/// Sent to put the camera back on the current page's frame, undoing any pan and zoom.
+pub struct ResetView;
+
+pub(crate) fn camera_setup(mut commands: Commands) {
+    commands
+        .spawn_bundle(Camera2dBundle::default())
+        .insert(PageCamera);
+}
+
+/// The position in the world under the cursor, if the cursor is in the window.
+pub fn cursor_world_position(
+    windows: &Windows,
+    camera: &Camera,
+    transform: &GlobalTransform,
+) -> Option<Vec2> {
+    let window = windows.get_primary()?;
+    let cursor = window.cursor_position()?;
+    let size = Vec2::new(window.width(), window.height());
+    let ndc = (cursor / size) * 2. - Vec2::ONE;
+    let ndc_to_world = transform.compute_matrix() * camera.projection_matrix().inverse();
+    Some(ndc_to_world.project_point3(ndc.extend(-1.)).truncate())
+}
+
+/// Mouse wheel zooms in and out around the cursor, dragging with the right or middle button
+/// pans, and Home sends [`ResetView`].
+pub(crate) fn camera_controls(
+    mut wheel: EventReader<MouseWheel>,
+    mut motion: EventReader<MouseMotion>,
+    buttons: Res<Input<MouseButton>>,
+    keys: Res<Input<KeyCode>>,
+    windows: Res<Windows>,
+    mut egui_context: ResMut<EguiContext>,
+    mut reset: EventWriter<ResetView>,
+    mut camera_query: Query<
+        (
+            &Camera,
+            &GlobalTransform,
+            &mut Transform,
+            &mut OrthographicProjection,
+        ),
+        With<PageCamera>,
+    >,
+) {
+    let ctx = egui_context.ctx_mut();
+    let over_ui = ctx.is_pointer_over_area() || ctx.wants_pointer_input();
+    let lines: f32 = wheel
+        .iter()
+        .map(|w| match w.unit {
+            MouseScrollUnit::Line => w.y,
+            MouseScrollUnit::Pixel => w.y / PIXELS_PER_LINE,
+        })
+        .sum();
+    let dragged: Vec2 = motion.iter().map(|w| w.delta).sum();
+    if keys.just_pressed(KeyCode::Home) {
+        reset.send(ResetView);
+    }
+    if over_ui {
+        return;
+    }
+    let window = match windows.get_primary() {
+        Some(window) => window,
+        None => return,
+    };
+    for (camera, global, mut transform, mut projection) in camera_query.iter_mut() {
+        let per_pixel = (projection.right - projection.left) * projection.scale / window.width();
+        if buttons.pressed(MouseButton::Right) || buttons.pressed(MouseButton::Middle) {
+            // Screen y points down while world y points up.
+            transform.translation += Vec3::new(-dragged.x, dragged.y, 0.) * per_pixel;
+        }
+        if lines != 0. {
+            let zoom = ZOOM_PER_LINE.powf(lines);
+            // Keep the point under the cursor where it is.
+            if let Some(cursor) = cursor_world_position(&windows, camera, global) {
+                let center = transform.translation.truncate();
+                let moved = center + (cursor - center) * (1. - zoom);
+                transform.translation = moved.extend(transform.translation.z);
+            }
+            projection.scale *= zoom;
+        }
+    }
+}
+
+/// Fit the camera to the current page's frame when the page changes or a [`ResetView`] is sent.
+pub(crate) fn move_camera(
+    page: Res<State<Page>>,
+    frames: Res<PageFrames>,
+    mut reset: EventReader<ResetView>,
+    mut camera_query: Query<(&mut Transform, &mut OrthographicProjection), With<PageCamera>>,
+) {
+    let reset = reset.iter().count() > 0;
+    if !reset && !page.is_changed() && !frames.is_changed() {
+        return;
+    }
+    let frame = frames.get(*page.current());
+    for (mut transform, mut projection) in camera_query.iter_mut() {
+        projection.scaling_mode = ScalingMode::Auto {
+            min_width: frame.size.x,
+            min_height: frame.size.y,
+        };
+        projection.scale = 1.;
+        transform.translation = frame.center.extend(transform.translation.z);
+    }
+}
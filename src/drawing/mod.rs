@@ -3,21 +3,57 @@
 
 use bevy::prelude::*;
 
-use crate::variables::binding::update_bindings;
+use crate::variables::binding::{interpolate_bindings, update_bindings};
+use crate::variables::SimulationStage;
 
+use self::axes::{update_axes, Axes};
+use self::boundarc::{update_bound_arcs, BoundArc};
+use self::boundarrow::{update_bound_arrows, BoundArrow};
+use self::boundbezier::{update_bound_beziers, BoundBezier};
 use self::boundcircle::{update_bound_circles, BoundCircle};
+use self::boundfunctionplot::{update_bound_function_plots, BoundFunctionPlot};
 use self::boundline::{update_bound_lines, BoundLine};
 use self::boundlocation::BoundLocation;
-use self::boundtracker::{update_bound_trackers, BoundTracker};
+use self::boundpolygon::{update_bound_polygons, BoundPolygon};
+use self::boundrect::{update_bound_rects, BoundRect};
+use self::boundstyle::{update_bound_styles, BoundStyle};
+use self::boundtext::{update_bound_texts, BoundText};
+use self::boundtracker::{sample_bound_trackers, update_bound_trackers, BoundTracker};
+use self::boundtrail::{
+    clear_trails_on_enter, sample_bound_trails, update_bound_trails, BoundTrail,
+};
+use self::camera::{camera_controls, camera_setup, move_camera, PageFrames, ResetView};
 
+/// Axes, ticks, grids and labels to read values off the other shapes
+pub mod axes;
+/// Arcs and sectors of circles with a bound center, radius, start and sweep
+pub mod boundarc;
+/// Lines with an arrowhead at their tip, for drawing vectors
+pub mod boundarrow;
+/// Quadratic and cubic Bézier curves with every point bound to variables
+pub mod boundbezier;
 /// Circles that bind their radius and location to variable values
 pub mod boundcircle;
+/// The curve of an expression over a range of one of its variables, in Cartesian or polar form
+pub mod boundfunctionplot;
 /// Lines that bind the coordinates of both their ends to variables
 pub mod boundline;
 /// A point that binds its co-ordinates to variable values
 pub mod boundlocation;
+/// Shapes through any number of bound vertices, open or closed
+pub mod boundpolygon;
+/// Rectangles that bind their center, width and height to variables
+pub mod boundrect;
+/// Colors, opacity and stroke widths bound to variable values
+pub mod boundstyle;
+/// Text next to the shapes, filled in from variable values
+pub mod boundtext;
 /// A line that tracks the last N values of a variable
 pub mod boundtracker;
+/// The fading, optionally looping path traced by a point bound to two variables
+pub mod boundtrail;
+/// The camera, each page's world-space frame, and panning and zooming
+pub mod camera;
 
 /// Adds a standard camera, binding update functions for the built-in bound components, and the systems for the pages.
 pub struct DrawingPlugin {
@@ -30,29 +66,56 @@ impl Plugin for DrawingPlugin {
         //     current_page: 1,
         //     num_pages: self.num_pages,
         // });
-        app.add_startup_system(camera_setup);
-        // app.add_system(camera_controls);
-        // app.add_system(move_camera);
+        app.init_resource::<PageFrames>()
+            .add_event::<ResetView>()
+            .add_startup_system(camera_setup)
+            .add_system(camera_controls)
+            .add_system(move_camera.after(camera_controls))
+            .add_system(clear_trails_on_enter);
+
+        app.add_system_set_to_stage(
+            SimulationStage,
+            SystemSet::new()
+                .label("sampling")
+                .after("variable_recalculation")
+                .with_system(update_bindings::<BoundTracker>.label("bind_trackers"))
+                .with_system(sample_bound_trackers.after("bind_trackers"))
+                .with_system(update_bindings::<BoundTrail>.label("bind_trails"))
+                .with_system(sample_bound_trails.after("bind_trails")),
+        );
 
         app.add_system_set(
             SystemSet::new()
                 .label("drawing")
-                .after("variable_recalculation")
-                .with_system(update_bindings::<BoundLine>.label("bind_lines"))
+                .with_system(interpolate_bindings::<BoundLine>.label("bind_lines"))
                 .with_system(update_bound_lines.after("bind_lines"))
-                .with_system(update_bindings::<BoundLocation>.label("bind_points"))
-                .with_system(update_bindings::<BoundCircle>.label("bind_circles"))
+                .with_system(interpolate_bindings::<BoundLocation>.label("bind_points"))
+                .with_system(interpolate_bindings::<BoundCircle>.label("bind_circles"))
                 .with_system(
                     update_bound_circles
                         .after("bind_points")
                         .after("bind_circles"),
                 )
-                .with_system(update_bindings::<BoundTracker>.label("bind_trackers"))
-                .with_system(update_bound_trackers.after("bind_trackers")),
+                .with_system(update_bound_trackers.after("bind_points"))
+                .with_system(update_bound_trails)
+                .with_system(interpolate_bindings::<BoundRect>.label("bind_rects"))
+                .with_system(update_bound_rects.after("bind_rects"))
+                .with_system(interpolate_bindings::<BoundPolygon>.label("bind_polygons"))
+                .with_system(update_bound_polygons.after("bind_polygons"))
+                .with_system(interpolate_bindings::<BoundArrow>.label("bind_arrows"))
+                .with_system(update_bound_arrows.after("bind_arrows"))
+                .with_system(interpolate_bindings::<BoundArc>.label("bind_arcs"))
+                .with_system(update_bound_arcs.after("bind_arcs"))
+                .with_system(interpolate_bindings::<BoundBezier>.label("bind_beziers"))
+                .with_system(update_bound_beziers.after("bind_beziers"))
+                .with_system(interpolate_bindings::<BoundFunctionPlot>.label("bind_plots"))
+                .with_system(update_bound_function_plots.after("bind_plots"))
+                .with_system(interpolate_bindings::<Axes>.label("bind_axes"))
+                .with_system(update_axes.after("bind_axes"))
+                .with_system(interpolate_bindings::<BoundText>.label("bind_texts"))
+                .with_system(update_bound_texts.after("bind_points").after("bind_texts"))
+                .with_system(interpolate_bindings::<BoundStyle>.label("bind_styles"))
+                .with_system(update_bound_styles.after("bind_styles")),
         );
     }
 }
-
-fn camera_setup(mut commands: Commands) {
-    commands.spawn_bundle(Camera2dBundle::default());
-}
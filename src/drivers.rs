@@ -0,0 +1,318 @@
+//! Reusable signal generators that drive independent variables from the clock of the page they
+//! are on.
+
+use std::f64::consts::TAU;
+use std::ops::RangeInclusive;
+
+use bevy::prelude::*;
+
+use crate::clock::{PageClocks, SimulationClock};
+use crate::variables::{Independent, SimulationStage, Variable};
+use crate::Page;
+
+/// Adds the systems for every driver component.
+pub struct DriverPlugin;
+
+impl Plugin for DriverPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system_set_to_stage(
+            SimulationStage,
+            SystemSet::new()
+                .label("drive")
+                .after("advance_page_clocks")
+                .before("variable_recalculation")
+                .with_system(drive_lfos)
+                .with_system(drive_noise)
+                .with_system(drive_random_walks)
+                .with_system(drive_sample_and_holds),
+        );
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Waveform {
+    Sine,
+    Triangle,
+    Square,
+    Sawtooth,
+}
+
+impl Waveform {
+    /// The value of the wave, between -1 and 1, at `phase` cycles.
+    pub fn at(self, phase: f64) -> f64 {
+        let phase = phase.rem_euclid(1.);
+        match self {
+            Waveform::Sine => (phase * TAU).sin(),
+            Waveform::Triangle => 1. - 4. * (phase - 0.5).abs(),
+            Waveform::Square => {
+                if phase < 0.5 {
+                    1.
+                } else {
+                    -1.
+                }
+            }
+            Waveform::Sawtooth => 2. * phase - 1.,
+        }
+    }
+}
+
+/// A low frequency oscillator: `offset + amplitude * waveform(frequency * t)`.
+#[derive(Component, Clone)]
+pub struct Lfo {
+    pub waveform: Waveform,
+    pub frequency: f64,
+    pub amplitude: f64,
+    pub offset: f64,
+    phase: f64,
+}
+
+impl Lfo {
+    pub fn new(waveform: Waveform, frequency: f64, amplitude: f64, offset: f64) -> Self {
+        Self {
+            waveform,
+            frequency,
+            amplitude,
+            offset,
+            phase: 0.,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum NoiseKind {
+    /// Random values at each whole step, smoothly blended in between.
+    Value,
+    /// Random slopes at each whole step, which wanders more naturally than value noise.
+    Perlin,
+}
+
+/// Smooth, seeded noise: `offset + amplitude * noise(frequency * t)`.
+#[derive(Component, Clone)]
+pub struct Noise {
+    pub kind: NoiseKind,
+    pub seed: u64,
+    pub frequency: f64,
+    pub amplitude: f64,
+    pub offset: f64,
+    position: f64,
+}
+
+impl Noise {
+    pub fn new(kind: NoiseKind, seed: u64, frequency: f64, amplitude: f64, offset: f64) -> Self {
+        Self {
+            kind,
+            seed,
+            frequency,
+            amplitude,
+            offset,
+            position: 0.,
+        }
+    }
+
+    /// The noise, between roughly -1 and 1, at `x`.
+    pub fn sample(&self, x: f64) -> f64 {
+        let cell = x.floor();
+        let f = x - cell;
+        let cell = cell as i64;
+        match self.kind {
+            NoiseKind::Value => {
+                let a = random(self.seed, cell);
+                let b = random(self.seed, cell + 1);
+                a + (b - a) * fade(f)
+            }
+            NoiseKind::Perlin => {
+                let a = random(self.seed, cell) * f;
+                let b = random(self.seed, cell + 1) * (f - 1.);
+                2. * (a + (b - a) * fade(f))
+            }
+        }
+    }
+}
+
+/// Moves a random distance up or down every `interval` seconds, staying within `bounds`.
+#[derive(Component, Clone)]
+pub struct RandomWalk {
+    pub seed: u64,
+    pub step_size: f64,
+    pub interval: f64,
+    pub bounds: RangeInclusive<f64>,
+    steps: i64,
+    since_step: f64,
+}
+
+impl RandomWalk {
+    pub fn new(seed: u64, step_size: f64, interval: f64, bounds: RangeInclusive<f64>) -> Self {
+        Self {
+            seed,
+            step_size,
+            interval,
+            bounds,
+            steps: 0,
+            since_step: 0.,
+        }
+    }
+}
+
+/// Copies the value of `source` every `period` seconds and holds it in between.
+#[derive(Component, Clone)]
+pub struct SampleAndHold {
+    pub source: Entity,
+    pub period: f64,
+    since_sample: Option<f64>,
+}
+
+impl SampleAndHold {
+    pub fn new(source: Entity, period: f64) -> Self {
+        Self {
+            source,
+            period,
+            since_sample: None,
+        }
+    }
+}
+
+/// How far a driver has got, saved in every recorded frame so that scrubbing back puts it where
+/// it was. A random walk's position is its variable's value, which is recorded anyway.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DriverState {
+    Lfo(f64),
+    Noise(f64),
+    RandomWalk { steps: i64, since_step: f64 },
+    SampleAndHold(Option<f64>),
+}
+
+impl DriverState {
+    /// The states of whichever drivers are on one entity.
+    pub fn save(
+        (lfo, noise, walk, hold): (
+            Option<&Lfo>,
+            Option<&Noise>,
+            Option<&RandomWalk>,
+            Option<&SampleAndHold>,
+        ),
+    ) -> Vec<Self> {
+        let mut states = Vec::new();
+        states.extend(lfo.map(|w| DriverState::Lfo(w.phase)));
+        states.extend(noise.map(|w| DriverState::Noise(w.position)));
+        states.extend(walk.map(|w| DriverState::RandomWalk {
+            steps: w.steps,
+            since_step: w.since_step,
+        }));
+        states.extend(hold.map(|w| DriverState::SampleAndHold(w.since_sample)));
+        states
+    }
+
+    /// Put the driver this state was saved from back the way it was.
+    pub fn restore(
+        self,
+        (lfo, noise, walk, hold): (
+            Option<Mut<Lfo>>,
+            Option<Mut<Noise>>,
+            Option<Mut<RandomWalk>>,
+            Option<Mut<SampleAndHold>>,
+        ),
+    ) {
+        match (self, lfo, noise, walk, hold) {
+            (DriverState::Lfo(phase), Some(mut lfo), ..) => lfo.phase = phase,
+            (DriverState::Noise(position), _, Some(mut noise), ..) => noise.position = position,
+            (DriverState::RandomWalk { steps, since_step }, _, _, Some(mut walk), _) => {
+                walk.steps = steps;
+                walk.since_step = since_step;
+            }
+            (DriverState::SampleAndHold(since_sample), .., Some(mut hold)) => {
+                hold.since_sample = since_sample;
+            }
+            _ => {}
+        }
+    }
+}
+
+/// How far the clock that drives a variable on `page` moved this step.
+fn page_delta(page: Option<&Page>, clock: &SimulationClock, clocks: &PageClocks) -> f64 {
+    page.map_or(clock.delta(), |w| clocks.get(*w).delta())
+}
+
+/// A deterministic pseudo-random number between -1 and 1 for `index`, using splitmix64.
+fn random(seed: u64, index: i64) -> f64 {
+    let mut z = seed
+        .wrapping_add((index as u64).wrapping_mul(0x9E37_79B9_7F4A_7C15))
+        .wrapping_add(0x9E37_79B9_7F4A_7C15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^= z >> 31;
+    (z >> 11) as f64 / (1u64 << 53) as f64 * 2. - 1.
+}
+
+/// Perlin's smootherstep, so blended noise has no visible corners at whole steps.
+fn fade(t: f64) -> f64 {
+    t * t * t * (t * (t * 6. - 15.) + 10.)
+}
+
+fn drive_lfos(
+    clock: Res<SimulationClock>,
+    clocks: Res<PageClocks>,
+    mut lfo_query: Query<(&mut Lfo, &mut Variable, Option<&Page>), With<Independent>>,
+) {
+    for (mut lfo, mut var, page) in lfo_query.iter_mut() {
+        let delta = page_delta(page, &clock, &clocks);
+        lfo.phase = (lfo.phase + delta * lfo.frequency).rem_euclid(1.);
+        var.set_value(lfo.offset + lfo.amplitude * lfo.waveform.at(lfo.phase));
+    }
+}
+
+fn drive_noise(
+    clock: Res<SimulationClock>,
+    clocks: Res<PageClocks>,
+    mut noise_query: Query<(&mut Noise, &mut Variable, Option<&Page>), With<Independent>>,
+) {
+    for (mut noise, mut var, page) in noise_query.iter_mut() {
+        let delta = page_delta(page, &clock, &clocks);
+        noise.position += delta * noise.frequency;
+        var.set_value(noise.offset + noise.amplitude * noise.sample(noise.position));
+    }
+}
+
+fn drive_random_walks(
+    clock: Res<SimulationClock>,
+    clocks: Res<PageClocks>,
+    mut walk_query: Query<(&mut RandomWalk, &mut Variable, Option<&Page>), With<Independent>>,
+) {
+    for (mut walk, mut var, page) in walk_query.iter_mut() {
+        let delta = page_delta(page, &clock, &clocks);
+        walk.since_step += delta;
+        while walk.interval > 0. && walk.since_step >= walk.interval {
+            walk.since_step -= walk.interval;
+            walk.steps += 1;
+            let step = random(walk.seed, walk.steps) * walk.step_size;
+            let value = (var.value() + step).clamp(*walk.bounds.start(), *walk.bounds.end());
+            var.set_value(value);
+        }
+    }
+}
+
+fn drive_sample_and_holds(
+    clock: Res<SimulationClock>,
+    clocks: Res<PageClocks>,
+    mut queries: ParamSet<(
+        Query<&Variable>,
+        Query<(&mut SampleAndHold, &mut Variable, Option<&Page>), With<Independent>>,
+    )>,
+) {
+    let sources: Vec<Entity> = queries.p1().iter().map(|w| w.0.source).collect();
+    let values: Vec<Option<f64>> = sources
+        .iter()
+        .map(|w| queries.p0().get(*w).ok().map(|v| v.value()))
+        .collect();
+    for ((mut hold, mut var, page), source) in queries.p1().iter_mut().zip(values) {
+        let delta = page_delta(page, &clock, &clocks);
+        let since_sample = hold.since_sample.map_or(hold.period, |w| w + delta);
+        if since_sample >= hold.period {
+            if let Some(value) = source {
+                var.set_value(value);
+            }
+            hold.since_sample = Some(since_sample - hold.period);
+        } else {
+            hold.since_sample = Some(since_sample);
+        }
+    }
+}
@@ -0,0 +1,188 @@
+//! Runs a page's variable graph without a window and writes the values it takes at every step to CSV.
+//!
+//! ```text
+//! fourier --headless --steps 1000 --page fourier --rows 3 --out trace.csv
+//! ```
+
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+use bevy::asset::AssetPlugin;
+use bevy::ecs::event::Events;
+use bevy::prelude::*;
+use bevy::time::TimePlugin;
+use bevy_turborand::GlobalRng;
+use strum::IntoEnumIterator;
+
+use crate::animation::AnimationPlugin;
+use crate::clock::{ClockPlugin, PageClocks};
+use crate::drivers::DriverPlugin;
+use crate::page1::Page1Plugin;
+use crate::page2::Page2Plugin;
+use crate::page3::Page3Plugin;
+use crate::page4::{NewRowEvent, Page4Plugin};
+use crate::variables::{group::Group, Variable, VariablePlugin};
+use crate::{Headless, Page};
+
+pub struct HeadlessOptions {
+    pub steps: usize,
+    pub page: Page,
+    pub out: Option<PathBuf>,
+    /// The names of the variables to write. All of the page's variables are written if empty.
+    pub variables: Vec<String>,
+    /// How many rows to add to the Fourier page before starting.
+    pub rows: usize,
+    pub seed: u64,
+}
+
+impl Default for HeadlessOptions {
+    fn default() -> Self {
+        Self {
+            steps: 1000,
+            page: Page::Simple,
+            out: None,
+            variables: Vec::new(),
+            rows: 0,
+            seed: 0,
+        }
+    }
+}
+
+impl HeadlessOptions {
+    /// Read the options following `--headless` on the command line.
+    pub fn from_args(args: impl IntoIterator<Item = String>) -> Result<Self, String> {
+        let mut options = Self::default();
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            let mut value = || args.next().ok_or(format!("{} needs a value", arg));
+            match arg.as_str() {
+                "--headless" => {}
+                "--steps" => options.steps = parse(&arg, value()?)?,
+                "--rows" => options.rows = parse(&arg, value()?)?,
+                "--seed" => options.seed = parse(&arg, value()?)?,
+                "--out" => options.out = Some(value()?.into()),
+                "--vars" => {
+                    options.variables = value()?.split(',').map(|w| w.trim().into()).collect()
+                }
+                "--page" => {
+                    let name = value()?;
+                    options.page = Page::iter()
+                        .find(|w| format!("{:?}", w).eq_ignore_ascii_case(&name))
+                        .ok_or(format!("There is no page called {}", name))?;
+                }
+                _ => return Err(format!("Unknown argument {}", arg)),
+            }
+        }
+        Ok(options)
+    }
+}
+
+fn parse<T: std::str::FromStr>(arg: &str, value: String) -> Result<T, String> {
+    value
+        .parse()
+        .map_err(|_| format!("{} expects a number, not {}", arg, value))
+}
+
+/// Move time on by exactly one simulation step per update, so runs do not depend on how fast
+/// the machine is.
+fn step_time(mut time: ResMut<Time>, mut last: Local<Option<Instant>>) {
+    let step =
+        Duration::from_nanos((1e9 / VariablePlugin::default().steps_per_second).ceil() as u64);
+    let now = last.map_or(time.startup(), |w| w + step);
+    time.update_with_instant(now);
+    *last = Some(now);
+}
+
+/// An app with the variable graphs of every page and none of the rendering, input or UI, showing `page`.
+///
+/// The first update runs the page setups without advancing the simulation. Every update after
+/// that runs exactly one simulation step.
+pub fn headless_app(page: Page, seed: u64) -> App {
+    let mut app = App::new();
+    app.insert_resource(Headless)
+        .add_plugins_with(MinimalPlugins, |group| group.disable::<TimePlugin>())
+        .init_resource::<Time>()
+        .add_system_to_stage(CoreStage::First, step_time)
+        .add_plugin(AssetPlugin)
+        .add_plugin(VariablePlugin::default())
+        .add_plugin(ClockPlugin)
+        .add_plugin(AnimationPlugin)
+        .add_plugin(DriverPlugin)
+        .add_state(page)
+        .add_plugin(Page1Plugin)
+        .add_plugin(Page2Plugin)
+        .add_plugin(Page3Plugin)
+        .add_plugin(Page4Plugin)
+        .insert_resource(GlobalRng::with_seed(seed));
+    app
+}
+
+/// Add `rows` seeded rows to the Fourier page. They are spawned on the next update.
+pub fn add_rows(app: &mut App, rows: usize) {
+    let mut events = app.world.resource_mut::<Events<NewRowEvent>>();
+    for _ in 0..rows {
+        events.send(NewRowEvent);
+    }
+}
+
+/// The variables on `page` whose names are in `names`, or all of them if `names` is empty,
+/// in the order they were spawned. Each comes with a column name that tells apart variables
+/// of the same name in different groups.
+pub fn page_columns(app: &mut App, page: Page, names: &[String]) -> Vec<(String, Entity)> {
+    let mut columns: Vec<_> = app
+        .world
+        .query::<(Entity, &Name, &Group, &Page, &Variable)>()
+        .iter(&app.world)
+        .filter(|w| *w.3 == page)
+        .filter(|w| names.is_empty() || names.iter().any(|n| n == w.1.as_str()))
+        .map(|(e, name, group, ..)| (format!("{}[{}]", name, group.0), e))
+        .collect();
+    columns.sort_by_key(|w| w.1.id());
+    columns
+}
+
+/// Run `steps` simulation steps, writing the page clock and every column to `out` as CSV.
+pub fn write_trace(
+    app: &mut App,
+    page: Page,
+    columns: &[(String, Entity)],
+    steps: usize,
+    out: &mut dyn Write,
+) -> io::Result<()> {
+    write!(out, "step,time")?;
+    for (name, _) in columns.iter() {
+        write!(out, ",{}", name)?;
+    }
+    writeln!(out)?;
+
+    for step in 1..=steps {
+        app.update();
+        let time = app.world.resource::<PageClocks>().get(page).elapsed();
+        write!(out, "{},{}", step, time)?;
+        for (_, entity) in columns.iter() {
+            let value = app
+                .world
+                .get::<Variable>(*entity)
+                .map_or(f64::NAN, |w| w.value());
+            write!(out, ",{}", value)?;
+        }
+        writeln!(out)?;
+    }
+    Ok(())
+}
+
+pub fn run(options: &HeadlessOptions) -> io::Result<()> {
+    let mut app = headless_app(options.page, options.seed);
+    add_rows(&mut app, options.rows);
+    app.update();
+
+    let columns = page_columns(&mut app, options.page, &options.variables);
+    let mut out: Box<dyn Write> = match &options.out {
+        Some(path) => Box::new(BufWriter::new(File::create(path)?)),
+        None => Box::new(BufWriter::new(io::stdout())),
+    };
+    write_trace(&mut app, options.page, &columns, options.steps, &mut out)?;
+    out.flush()
+}
@@ -0,0 +1,218 @@
+//! Undo and redo for the changes made to variables through the inspectors, kept separately for every page.
+
+use bevy::prelude::*;
+use bevy::utils::HashMap;
+use bevy_egui::{egui, EguiContext};
+
+use crate::variables::Variable;
+use crate::Page;
+
+/// Adds the [`EditHistory`], its keyboard shortcuts and the history panel.
+///
+/// Every system that sends [`SetFromOutside`] is labelled `"set_from_outside"`, so that inspectors
+/// can run after it and show the new value before they write their sliders back.
+pub struct HistoryPlugin;
+
+impl Plugin for HistoryPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<EditHistory>()
+            .add_event::<SetFromOutside>()
+            .add_system(close_history_steps)
+            .add_system(history_controls.label("set_from_outside"))
+            .add_system(history_panel.label("set_from_outside"));
+    }
+}
+
+/// A single step in the history: `variable` was moved from `before` to `after`.
+#[derive(Clone, Copy, Debug)]
+pub struct Edit {
+    pub variable: Entity,
+    pub before: f64,
+    pub after: f64,
+}
+
+/// Sent when something other than its inspector sets `variable` to `value`, so the inspector can
+/// show the new value. Undo and redo, bookmarks, scrubbing the recording and dragging handles all
+/// send it.
+pub struct SetFromOutside {
+    pub variable: Entity,
+    pub value: f64,
+}
+
+#[derive(Default)]
+struct PageHistory {
+    undo: Vec<Edit>,
+    redo: Vec<Edit>,
+    /// Whether the latest step may still absorb edits, while a slider is being dragged.
+    open: bool,
+}
+
+#[derive(Default)]
+pub struct EditHistory(HashMap<Page, PageHistory>);
+
+impl EditHistory {
+    /// Set `var` to `value` on behalf of the user, recording the change on `page`. Does nothing
+    /// if `var` already has that value, so it is not marked as changed.
+    pub fn edit(&mut self, page: Page, entity: Entity, var: &mut Variable, value: f64) {
+        let before = var.value();
+        if before == value {
+            return;
+        }
+        var.set_value(value);
+        self.record(page, entity, before, value);
+    }
+
+    /// Record that `variable` was moved from `before` to `after`. Changes to the same variable
+    /// while the latest step is still open are merged into it.
+    pub fn record(&mut self, page: Page, variable: Entity, before: f64, after: f64) {
+        if before == after {
+            return;
+        }
+        let history = self.0.entry(page).or_default();
+        history.redo.clear();
+        match history.undo.last_mut() {
+            Some(last) if history.open && last.variable == variable => last.after = after,
+            _ => history.undo.push(Edit {
+                variable,
+                before,
+                after,
+            }),
+        }
+        history.open = true;
+    }
+
+    /// Stop merging edits into the latest step of every page.
+    pub fn close(&mut self) {
+        for history in self.0.values_mut() {
+            history.open = false;
+        }
+    }
+
+    /// Take back the latest step on `page`, returning it so its `before` value can be restored.
+    pub fn undo(&mut self, page: Page) -> Option<Edit> {
+        let history = self.0.get_mut(&page)?;
+        history.open = false;
+        let edit = history.undo.pop()?;
+        history.redo.push(edit);
+        Some(edit)
+    }
+
+    /// Reapply the latest step taken back on `page`, returning it so its `after` value can be restored.
+    pub fn redo(&mut self, page: Page) -> Option<Edit> {
+        let history = self.0.get_mut(&page)?;
+        history.open = false;
+        let edit = history.redo.pop()?;
+        history.undo.push(edit);
+        Some(edit)
+    }
+
+    /// The steps on `page` that can be undone, oldest first.
+    pub fn undo_steps(&self, page: Page) -> &[Edit] {
+        self.0
+            .get(&page)
+            .map(|w| w.undo.as_slice())
+            .unwrap_or_default()
+    }
+
+    /// The steps on `page` that can be redone, next first.
+    pub fn redo_steps(&self, page: Page) -> impl Iterator<Item = &Edit> {
+        self.0
+            .get(&page)
+            .into_iter()
+            .flat_map(|w| w.redo.iter().rev())
+    }
+}
+
+/// Close the open step once the pointer is released, so each slider drag is one step.
+fn close_history_steps(mut history: ResMut<EditHistory>, mut egui_context: ResMut<EguiContext>) {
+    if !egui_context.ctx_mut().input().pointer.any_down() {
+        history.close();
+    }
+}
+
+fn restore(
+    var_query: &mut Query<&mut Variable>,
+    outside: &mut EventWriter<SetFromOutside>,
+    variable: Entity,
+    value: f64,
+) {
+    if let Ok(mut var) = var_query.get_mut(variable) {
+        var.set_value(value);
+        outside.send(SetFromOutside { variable, value });
+    }
+}
+
+/// Ctrl+Z undoes and Ctrl+Shift+Z redoes the latest change on the current page.
+fn history_controls(
+    mut history: ResMut<EditHistory>,
+    input: Res<Input<KeyCode>>,
+    page: Res<State<Page>>,
+    mut var_query: Query<&mut Variable>,
+    mut outside: EventWriter<SetFromOutside>,
+) {
+    let ctrl = input.any_pressed([KeyCode::LControl, KeyCode::RControl]);
+    let shift = input.any_pressed([KeyCode::LShift, KeyCode::RShift]);
+    if !ctrl || !input.just_pressed(KeyCode::Z) {
+        return;
+    }
+    if shift {
+        if let Some(edit) = history.redo(*page.current()) {
+            restore(&mut var_query, &mut outside, edit.variable, edit.after);
+        }
+    } else if let Some(edit) = history.undo(*page.current()) {
+        restore(&mut var_query, &mut outside, edit.variable, edit.before);
+    }
+}
+
+/// Lists the steps on the current page. Clicking a step undoes or redoes everything up to it.
+fn history_panel(
+    mut history: ResMut<EditHistory>,
+    mut egui_context: ResMut<EguiContext>,
+    page: Res<State<Page>>,
+    names: Query<&Name>,
+    mut var_query: Query<&mut Variable>,
+    mut outside: EventWriter<SetFromOutside>,
+) {
+    let page = *page.current();
+    let describe = |edit: &Edit| {
+        let name = names.get(edit.variable).map_or("?", |w| w.as_str());
+        format!("{}: {:.2} -> {:.2}", name, edit.before, edit.after)
+    };
+    let mut undo_to = None;
+    let mut redo_count = 0;
+    egui::Window::new("History")
+        .anchor(egui::Align2::RIGHT_BOTTOM, [-10.0, -10.0])
+        .show(egui_context.ctx_mut(), |ui| {
+            let undo_steps = history.undo_steps(page);
+            if ui
+                .selectable_label(undo_steps.is_empty(), "Start")
+                .clicked()
+            {
+                undo_to = Some(0);
+            }
+            for (index, edit) in undo_steps.iter().enumerate() {
+                let latest = index + 1 == undo_steps.len();
+                if ui.selectable_label(latest, describe(edit)).clicked() {
+                    undo_to = Some(index + 1);
+                }
+            }
+            for (index, edit) in history.redo_steps(page).enumerate() {
+                let label = egui::RichText::new(describe(edit)).weak();
+                if ui.selectable_label(false, label).clicked() {
+                    redo_count = index + 1;
+                }
+            }
+        });
+
+    if let Some(len) = undo_to {
+        while history.undo_steps(page).len() > len {
+            let edit = history.undo(page).unwrap();
+            restore(&mut var_query, &mut outside, edit.variable, edit.before);
+        }
+    }
+    for _ in 0..redo_count {
+        if let Some(edit) = history.redo(page) {
+            restore(&mut var_query, &mut outside, edit.variable, edit.after);
+        }
+    }
+}
@@ -0,0 +1,229 @@
+//! Grabbing shapes with the mouse and dragging them to set the independent variables behind them.
+
+use std::f32::consts::TAU;
+use std::sync::Arc;
+
+use bevy::prelude::*;
+use bevy::window::CursorIcon;
+use bevy_egui::EguiContext;
+
+use crate::drawing::boundcircle::BoundCircle;
+use crate::drawing::camera::{cursor_world_position, PageCamera};
+use crate::history::{EditHistory, SetFromOutside};
+use crate::variables::variable::ValueRange;
+use crate::variables::{Independent, Variable};
+use crate::Page;
+
+/// Adds hit-testing and dragging for every [`DragHandle`]. Dragging sends [`SetFromOutside`], so
+/// it is labelled `"set_from_outside"` along with the history's systems.
+pub struct InteractionPlugin;
+
+impl Plugin for InteractionPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(update_handle_states.label("handle_states"))
+            .add_system(
+                drag_handles
+                    .label("set_from_outside")
+                    .after("handle_states"),
+            );
+    }
+}
+
+/// Whether the cursor is over a handle, or is dragging it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HandleState {
+    Idle,
+    Hovered,
+    Dragged,
+}
+
+/// How the cursor's movement while dragging turns into changes of a handle's variables.
+#[derive(Clone)]
+pub enum DragMapping {
+    /// The variables move by the distance the cursor moves along x and y, times these factors.
+    Linear(Vec2),
+    /// The first variable turns with the cursor around this point, in radians.
+    Angle(Vec2),
+    /// The variables move by as much as the x and y of this function of the cursor position.
+    Custom(Arc<dyn Fn(Vec2) -> Vec2 + Send + Sync>),
+}
+
+impl DragMapping {
+    /// How far the variables should move for the cursor moving from `from` to `to`.
+    fn delta(&self, from: Vec2, to: Vec2) -> Vec2 {
+        match self {
+            DragMapping::Linear(scale) => (to - from) * *scale,
+            DragMapping::Angle(pivot) => Vec2::new((from - *pivot).angle_between(to - *pivot), 0.),
+            DragMapping::Custom(map) => map(to) - map(from),
+        }
+    }
+}
+
+/// Lets the shape on the same entity be grabbed with the left mouse button, writing the drag
+/// to one or two independent variables.
+///
+/// The shape is hit anywhere within `radius` of its position, or within its radius if it is a
+/// [`BoundCircle`] and that is larger.
+#[derive(Component, Clone)]
+pub struct DragHandle {
+    x: Option<Entity>,
+    y: Option<Entity>,
+    pub mapping: DragMapping,
+    pub radius: f32,
+    state: HandleState,
+    last: Option<Vec2>,
+}
+
+impl DragHandle {
+    /// Drag `x` and `y` with the cursor, one unit per unit.
+    pub fn new(x: Option<Entity>, y: Option<Entity>) -> Self {
+        Self {
+            x,
+            y,
+            mapping: DragMapping::Linear(Vec2::ONE),
+            radius: 10.,
+            state: HandleState::Idle,
+            last: None,
+        }
+    }
+
+    /// Turn `angle` as the cursor is dragged around `pivot`.
+    pub fn angle(angle: Entity, pivot: Vec2) -> Self {
+        Self::new(Some(angle), None).mapping(DragMapping::Angle(pivot))
+    }
+
+    pub fn mapping(mut self, mapping: DragMapping) -> Self {
+        self.mapping = mapping;
+        self
+    }
+
+    pub fn radius(mut self, radius: f32) -> Self {
+        self.radius = radius;
+        self
+    }
+
+    pub fn state(&self) -> HandleState {
+        self.state
+    }
+}
+
+/// Hover the handle nearest the cursor, and grab it when the left button is pressed.
+fn update_handle_states(
+    mut windows: ResMut<Windows>,
+    mut icon: Local<Option<CursorIcon>>,
+    buttons: Res<Input<MouseButton>>,
+    mut egui_context: ResMut<EguiContext>,
+    camera_query: Query<(&Camera, &GlobalTransform), With<PageCamera>>,
+    mut handle_query: Query<(
+        Entity,
+        &mut DragHandle,
+        &GlobalTransform,
+        &ComputedVisibility,
+        Option<&BoundCircle>,
+    )>,
+) {
+    let cursor = camera_query
+        .get_single()
+        .ok()
+        .and_then(|(camera, transform)| cursor_world_position(&windows, camera, transform));
+    let released = !buttons.pressed(MouseButton::Left);
+    let dragging = handle_query
+        .iter()
+        .any(|w| w.1.state == HandleState::Dragged);
+
+    if dragging && !released {
+        return;
+    }
+    let over_ui = egui_context.ctx_mut().is_pointer_over_area();
+    let hovered = cursor.filter(|_| !over_ui).and_then(|cursor| {
+        handle_query
+            .iter()
+            .filter(|w| w.3.is_visible())
+            .map(|(entity, handle, transform, _, circle)| {
+                let radius = circle
+                    .map_or(0., |w| w.radius_value.abs())
+                    .max(handle.radius);
+                let distance = transform.translation().truncate().distance(cursor);
+                (distance, radius, entity)
+            })
+            .filter(|w| w.0 <= w.1)
+            .min_by(|a, b| a.0.total_cmp(&b.0))
+            .map(|w| w.2)
+    });
+    let grabbed = buttons.just_pressed(MouseButton::Left);
+
+    for (entity, mut handle, ..) in handle_query.iter_mut() {
+        let state = match hovered {
+            Some(hit) if hit == entity && grabbed => HandleState::Dragged,
+            Some(hit) if hit == entity => HandleState::Hovered,
+            _ => HandleState::Idle,
+        };
+        if handle.state != state {
+            handle.state = state;
+            handle.last = cursor.filter(|_| state == HandleState::Dragged);
+        }
+    }
+
+    let new_icon = match (hovered.is_some(), grabbed) {
+        (true, true) => CursorIcon::Grabbing,
+        (true, false) => CursorIcon::Grab,
+        _ => CursorIcon::Default,
+    };
+    // Only touch the cursor when it changes, so other systems are free to set it.
+    if *icon != Some(new_icon) {
+        if let Some(window) = windows.get_primary_mut() {
+            window.set_cursor_icon(new_icon);
+        }
+        *icon = Some(new_icon);
+    }
+}
+
+/// Move the variables of the handle being dragged by as much as the cursor moved.
+fn drag_handles(
+    windows: Res<Windows>,
+    page: Res<State<Page>>,
+    mut history: ResMut<EditHistory>,
+    mut outside: EventWriter<SetFromOutside>,
+    camera_query: Query<(&Camera, &GlobalTransform), With<PageCamera>>,
+    mut handle_query: Query<(&mut DragHandle, Option<&Page>)>,
+    mut var_query: Query<(&mut Variable, Option<&ValueRange>), With<Independent>>,
+) {
+    let cursor = match camera_query
+        .get_single()
+        .ok()
+        .and_then(|(camera, transform)| cursor_world_position(&windows, camera, transform))
+    {
+        Some(cursor) => cursor,
+        None => return,
+    };
+    for (mut handle, handle_page) in handle_query.iter_mut() {
+        let last = match handle.last {
+            Some(last) if handle.state == HandleState::Dragged => last,
+            _ => continue,
+        };
+        handle.last = Some(cursor);
+        let delta = handle.mapping.delta(last, cursor);
+        let wraps = matches!(handle.mapping, DragMapping::Angle(_));
+        let page = handle_page.copied().unwrap_or(*page.current());
+        for (variable, delta) in [(handle.x, delta.x), (handle.y, delta.y)] {
+            let variable = match variable {
+                Some(variable) => variable,
+                None => continue,
+            };
+            if let Ok((mut var, range)) = var_query.get_mut(variable) {
+                let mut value = var.value() + delta as f64;
+                if let Some(ValueRange(range)) = range {
+                    let (start, end) = (*range.start(), *range.end());
+                    // Angles go round rather than stopping at the ends of a full turn.
+                    value = if wraps && ((end - start) - TAU as f64).abs() < 1e-6 {
+                        start + (value - start).rem_euclid(end - start)
+                    } else {
+                        value.clamp(start, end)
+                    };
+                }
+                history.edit(page, variable, &mut var, value);
+                outside.send(SetFromOutside { variable, value });
+            }
+        }
+    }
+}
@@ -0,0 +1,145 @@
+use animation::AnimationPlugin;
+use bevy::prelude::*;
+use bevy::{asset::AssetServerSettings, prelude::Component};
+use bevy_egui::EguiPlugin;
+use bevy_prototype_lyon::prelude::*;
+use bookmarks::BookmarkPlugin;
+use clock::ClockPlugin;
+use drawing::DrawingPlugin;
+use drivers::DriverPlugin;
+use history::HistoryPlugin;
+use interaction::InteractionPlugin;
+use page1::Page1Plugin;
+use page2::Page2Plugin;
+use page3::Page3Plugin;
+use page4::Page4Plugin;
+use recording::RecordingPlugin;
+use serde::{Deserialize, Serialize};
+use strum::IntoEnumIterator;
+use strum_macros::EnumIter;
+use variables::debug::DebugPlugin;
+use variables::variable::Variable;
+use variables::VariablePlugin;
+
+pub mod animation;
+pub mod bookmarks;
+pub mod clock;
+pub mod drawing;
+pub mod drivers;
+pub mod headless;
+pub mod history;
+pub mod interaction;
+pub mod page1;
+pub mod page2;
+pub mod page3;
+pub mod page4;
+pub mod recording;
+pub mod variables;
+
+const GLOBAL: usize = 0;
+
+#[derive(Debug, Clone, Eq, PartialEq, Hash, EnumIter, Copy, Component, Serialize, Deserialize)]
+pub enum Page {
+    Simple,
+    Combination,
+    Game,
+    Fourier,
+}
+
+/// Inserted before the plugins when running without a window, so they leave out their input and UI systems.
+pub struct Headless;
+
+#[derive(Component)]
+pub(crate) struct EquationText {
+    variables: Vec<Entity>,
+    template: &'static str,
+}
+
+/// Marks the variable holding the simulation clock's time.
+#[derive(Component)]
+pub struct Time;
+
+/// The full app, with a window, every page and all of the UI.
+pub fn app() -> App {
+    let mut app = App::new();
+    app.insert_resource(AssetServerSettings {
+        watch_for_changes: true,
+        ..default()
+    })
+    .insert_resource(Msaa { samples: 4 })
+    .add_plugins(DefaultPlugins)
+    .add_plugin(ShapePlugin)
+    .add_plugin(EguiPlugin)
+    .add_plugin(VariablePlugin::default())
+    .add_plugin(ClockPlugin)
+    .add_plugin(AnimationPlugin)
+    .add_plugin(DriverPlugin)
+    .add_plugin(HistoryPlugin)
+    .add_plugin(BookmarkPlugin)
+    .add_plugin(RecordingPlugin::default())
+    .add_plugin(DrawingPlugin { num_pages: 4 })
+    .add_plugin(InteractionPlugin)
+    .add_plugin(DebugPlugin {
+        variables: false,
+        bindings: false,
+    })
+    .add_state(Page::Simple)
+    .add_plugin(Page1Plugin)
+    .add_plugin(Page2Plugin)
+    .add_plugin(Page3Plugin)
+    .add_plugin(Page4Plugin)
+    .add_system(page_system)
+    .add_system(page_enter)
+    .add_system(update_text);
+    app
+}
+
+fn page_system(mut page: ResMut<State<Page>>, input: Res<Input<KeyCode>>) {
+    let mut movement = 0;
+    if input.just_pressed(KeyCode::D) {
+        movement += 1;
+    }
+    if input.just_pressed(KeyCode::A) {
+        movement -= 1;
+    }
+    if movement != 0 {
+        let pages: Vec<Page> = Page::iter().collect();
+        let mut current_index = pages.iter().position(|w| *w == *page.current()).unwrap() as isize;
+        current_index = (current_index + movement)
+            .max(0)
+            .min((pages.len() - 1) as isize);
+        match page.set(*pages.get(current_index as usize).unwrap()) {
+            Ok(_) => (),
+            Err(_) => (),
+        };
+    }
+}
+
+fn page_enter(mut page_query: Query<(&Page, &mut Visibility)>, current_page: Res<State<Page>>) {
+    if current_page.is_changed() {
+        for (page, mut visibility) in page_query.iter_mut() {
+            visibility.is_visible = page == current_page.current();
+        }
+    }
+}
+
+pub(crate) fn update_text(
+    mut text_query: Query<(&mut Text, &EquationText), With<Page>>,
+    var_query: Query<&Variable>,
+) {
+    for (mut text, equation) in text_query.iter_mut() {
+        let mut variables: Vec<_> = equation
+            .variables
+            .iter()
+            .map(|w| format!("{}", var_query.get(*w).unwrap().value()))
+            .collect();
+        variables.insert(0, "".into());
+
+        let pairs = variables.iter().zip(equation.template.split("$"));
+        let result: String = pairs
+            .map(|(a, b)| format!("{}{}", a, b))
+            .collect::<Vec<_>>()
+            .join("");
+        text.sections[0].value = result;
+    }
+}
@@ -1,126 +1,16 @@
-use bevy::prelude::*;
-use bevy::{asset::AssetServerSettings, prelude::Component};
-use bevy_egui::EguiPlugin;
-use bevy_prototype_lyon::prelude::*;
-use drawing::DrawingPlugin;
-use page1::Page1Plugin;
-use page2::Page2Plugin;
-use page3::Page3Plugin;
-use page4::Page4Plugin;
-use strum::IntoEnumIterator;
-use strum_macros::EnumIter;
-use variables::debug::DebugPlugin;
-use variables::variable::Variable;
-use variables::VariablePlugin;
-
-mod drawing;
-mod page1;
-mod page2;
-mod page3;
-mod page4;
-mod variables;
-
-const GLOBAL: usize = 0;
-
-#[derive(Debug, Clone, Eq, PartialEq, Hash, EnumIter, Copy, Component)]
-pub(crate) enum Page {
-    Simple,
-    Combination,
-    Game,
-    Fourier,
-}
-
-#[derive(Component)]
-pub(crate) struct EquationText {
-    variables: Vec<Entity>,
-    template: &'static str,
-}
-
-#[derive(Component)]
-pub struct Time;
+use fourier::headless::{self, HeadlessOptions};
 
 fn main() {
-    App::new()
-        .insert_resource(AssetServerSettings {
-            watch_for_changes: true,
-            ..default()
-        })
-        .insert_resource(Msaa { samples: 4 })
-        .add_plugins(DefaultPlugins)
-        .add_plugin(ShapePlugin)
-        .add_plugin(EguiPlugin)
-        .add_plugin(VariablePlugin)
-        .add_plugin(DrawingPlugin { num_pages: 4 })
-        .add_plugin(DebugPlugin {
-            variables: false,
-            bindings: false,
-        })
-        .add_state(Page::Simple)
-        .add_plugin(Page1Plugin)
-        .add_plugin(Page2Plugin)
-        .add_plugin(Page3Plugin)
-        .add_plugin(Page4Plugin)
-        .add_system(time_update)
-        .add_system(page_system)
-        .add_system(page_enter)
-        .add_system(update_text)
-        .run();
-}
-
-fn page_system(mut page: ResMut<State<Page>>, input: Res<Input<KeyCode>>) {
-    let mut movement = 0;
-    if input.just_pressed(KeyCode::D) {
-        movement += 1;
-    }
-    if input.just_pressed(KeyCode::A) {
-        movement -= 1;
-    }
-    if movement != 0 {
-        let pages: Vec<Page> = Page::iter().collect();
-        let mut current_index = pages.iter().position(|w| *w == *page.current()).unwrap() as isize;
-        current_index = (current_index + movement)
-            .max(0)
-            .min((pages.len() - 1) as isize);
-        match page.set(*pages.get(current_index as usize).unwrap()) {
-            Ok(_) => (),
-            Err(_) => (),
-        };
-    }
-}
-
-fn page_enter(mut page_query: Query<(&Page, &mut Visibility)>, current_page: Res<State<Page>>) {
-    if current_page.is_changed() {
-        for (page, mut visibility) in page_query.iter_mut() {
-            visibility.is_visible = page == current_page.current();
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.iter().any(|w| w == "--headless") {
+        let result = HeadlessOptions::from_args(args)
+            .and_then(|options| headless::run(&options).map_err(|e| e.to_string()));
+        if let Err(e) = result {
+            eprintln!("{}", e);
+            std::process::exit(1);
         }
+        return;
     }
-}
-
-fn time_update(mut time_query: Query<&mut Variable, With<Time>>) {
-    let delta = 0.02;
-    for mut var in time_query.iter_mut() {
-        let old_value = (*var).value();
-        var.set_value(old_value + delta);
-    }
-}
 
-pub(crate) fn update_text(
-    mut text_query: Query<(&mut Text, &EquationText), With<Page>>,
-    var_query: Query<&Variable>,
-) {
-    for (mut text, equation) in text_query.iter_mut() {
-        let mut variables: Vec<_> = equation
-            .variables
-            .iter()
-            .map(|w| format!("{}", var_query.get(*w).unwrap().value()))
-            .collect();
-        variables.insert(0, "".into());
-
-        let pairs = variables.iter().zip(equation.template.split("$"));
-        let result: String = pairs
-            .map(|(a, b)| format!("{}{}", a, b))
-            .collect::<Vec<_>>()
-            .join("");
-        text.sections[0].value = result;
-    }
+    fourier::app().run();
 }
@@ -4,17 +4,19 @@ use bevy::prelude::*;
 use bevy_egui::{egui, EguiContext};
 use bevy_prototype_lyon::{prelude::*, shapes::Circle};
 
+use crate::animation::{Animation, Easing, Playback};
+use crate::clock::PageClocks;
+use crate::drawing::axes::{Axes, LabelFormat};
 use crate::drawing::boundcircle::BoundCircle;
 use crate::drawing::boundline::BoundLine;
 use crate::drawing::boundlocation::BoundLocation;
+use crate::drawing::boundstyle::{BoundStyle, Channel, StyleBinding};
+use crate::drawing::boundtext::BoundText;
 use crate::drawing::boundtracker::BoundTracker;
-use crate::variables::lambda::*;
-use crate::variables::{
-    group::Group,
-    variable::{dependent, independent},
-    Variable,
-};
-use crate::{EquationText, Page, Time, GLOBAL};
+use crate::history::{EditHistory, SetFromOutside};
+use crate::interaction::DragHandle;
+use crate::variables::{builder::VarScope, group::Group, Variable};
+use crate::{expr, EquationText, Headless, Page};
 const PAGE1: usize = 1;
 
 #[derive(Component)]
@@ -28,11 +30,16 @@ pub struct Page1Plugin;
 
 impl Plugin for Page1Plugin {
     fn build(&self, app: &mut App) {
-        app.add_system(update_page1_inspector)
-            .add_system(update_page1_variables_from_gui)
-            .add_startup_system(page1_setup)
+        app.add_startup_system(page1_setup)
             // .add_system_set(SystemSet::on_enter(Page::Simple).with_system(page_enter))
             .insert_resource(Page1Inspector::default());
+        if !app.world.contains_resource::<Headless>() {
+            // Show values set from outside before drawing the sliders, and write back after, so
+            // a value set this frame is not overwritten by the slider's old one.
+            app.add_system(update_page1_inspector_from_outside.after("set_from_outside"))
+                .add_system(update_page1_inspector.after(update_page1_inspector_from_outside))
+                .add_system(update_page1_variables_from_gui.after(update_page1_inspector));
+        }
     }
 }
 
@@ -46,45 +53,58 @@ macro_rules! build {
     };
 }
 
-fn page1_setup(mut commands: Commands, asset_server: Res<AssetServer>) {
-    let global = Group(GLOBAL);
-    let pagegroup = Group(PAGE1);
-    let time = independent(&mut commands, &global, "time", 0.);
-    let phase = independent(&mut commands, &pagegroup, "phase", 0.);
-    let freq = independent(&mut commands, &pagegroup, "freq", 2.);
-    let amp = independent(&mut commands, &pagegroup, "amp", 30.);
-    let circle_x = independent(&mut commands, &pagegroup, "circle_x", -200.);
-    let point_rad = independent(&mut commands, &pagegroup, "point_rad", 10.);
-    let zero = independent(&mut commands, &pagegroup, "0", 0.);
-    let theta = dependent(
-        &mut commands,
-        &pagegroup,
-        "theta",
-        Mod(Add(Var(phase), Mul(Var(time), Var(freq))), Num(2. * PI)),
-    );
-    let cos_theta = dependent(
-        &mut commands,
-        &pagegroup,
-        "cos(theta)",
-        Mul(Var(amp), Cos(Var(theta))),
-    );
-    let sin_theta = dependent(
-        &mut commands,
-        &pagegroup,
-        "sin(theta)",
-        Mul(Var(amp), Sin(Var(theta))), // sin(theta) * amp
-    );
-    let circle_cos = dependent(
-        &mut commands,
-        &pagegroup,
-        "circle_cos",
-        Add(Var(circle_x), Var(cos_theta)),
-    );
-
-    commands.entity(time).insert(Time);
-    commands.entity(amp).insert(Amp);
-    commands.entity(freq).insert(Freq);
-    commands.entity(phase).insert(Phase);
+fn page1_setup(mut commands: Commands, clocks: Res<PageClocks>, asset_server: Res<AssetServer>) {
+    let vars = VarScope::new(Group(PAGE1), Page::Simple);
+    let time = clocks.variable(Page::Simple);
+    let phase = vars
+        .independent("phase")
+        .range(0. ..=(PI * 2.))
+        .tag(Phase)
+        .spawn(&mut commands);
+    let freq = vars
+        .independent("freq")
+        .value(2.)
+        .range(1.0..=30.)
+        .tag(Freq)
+        .spawn(&mut commands);
+    let amp = vars
+        .independent("amp")
+        .value(30.)
+        .range(0.5..=100.)
+        .tag(Amp)
+        .spawn(&mut commands);
+    let circle_x = vars
+        .independent("circle_x")
+        .value(-200.)
+        .spawn(&mut commands);
+    let point_rad = vars
+        .independent("point_rad")
+        .value(10.)
+        .spawn(&mut commands);
+    // The handle on top of the circle pulses, to show that it can be grabbed.
+    let handle_rad = vars
+        .independent("handle_rad")
+        .value(6.)
+        .tag(
+            Animation::new(Playback::PingPong)
+                .key(0., 6., Easing::Linear)
+                .key(0.8, 9., Easing::EaseInOut),
+        )
+        .spawn(&mut commands);
+    let zero = vars.independent("0").spawn(&mut commands);
+    let theta = vars
+        .dependent("theta", expr!((phase + time * freq) % { 2. * PI }))
+        .wraps(2. * PI)
+        .spawn(&mut commands);
+    let cos_theta = vars
+        .dependent("cos(theta)", expr!(amp * cos(theta)))
+        .spawn(&mut commands);
+    let sin_theta = vars
+        .dependent("sin(theta)", expr!(amp * sin(theta)))
+        .spawn(&mut commands);
+    let circle_cos = vars
+        .dependent("circle_cos", expr!(circle_x + cos_theta))
+        .spawn(&mut commands);
 
     let circle = Circle::default();
 
@@ -102,15 +122,58 @@ fn page1_setup(mut commands: Commands, asset_server: Res<AssetServer>) {
         ))
         .insert(Page::Simple)
         .insert(BoundCircle::new(point_rad))
-        .insert(BoundLocation::new(circle_cos, sin_theta));
+        .insert(BoundLocation::new(circle_cos, sin_theta))
+        // The point's color goes once round the color wheel per turn, following the angle.
+        .insert(BoundStyle::stroke(Color::RED, 3.).bind(StyleBinding::Stroke(Channel::Hue), theta))
+        .insert(DragHandle::angle(phase, Vec2::new(-200., 0.)));
+
+    commands
+        .spawn_bundle(build!(circle))
+        .insert(Page::Simple)
+        .insert(BoundCircle::new(handle_rad))
+        .insert(BoundLocation::new(circle_x, amp))
+        .insert(DragHandle::new(None, Some(amp)));
+
+    commands
+        .spawn_bundle(Text2dBundle::default())
+        .insert(Page::Simple)
+        .insert(
+            BoundText::new("A = {amp:.0}")
+                .var("amp", amp)
+                .font(asset_server.load("FiraSans-Bold.ttf"))
+                .offset(Vec2::new(15., 0.)),
+        )
+        .insert(BoundLocation::new(circle_x, amp));
 
     let path_builder = PathBuilder::new();
     let line = path_builder.build();
 
-    commands
+    let tracker = commands
         .spawn_bundle(build!(line))
         .insert(Page::Simple)
-        .insert(BoundTracker::new(sin_theta, 300));
+        .insert(BoundTracker::new(sin_theta, 300))
+        .id();
+
+    commands
+        .spawn_bundle(GeometryBuilder::build_as(
+            &line,
+            DrawMode::Stroke(StrokeMode::new(Color::GRAY, 1.)),
+            Transform::from_xyz(0., 0., -0.1),
+        ))
+        .insert(Page::Simple)
+        .insert(
+            Axes::new((0., 0.), (-100., 100.), Vec2::new(0.5, 50.))
+                .labels(
+                    LabelFormat::Decimal,
+                    LabelFormat::Decimal,
+                    TextStyle {
+                        font: asset_server.load("FiraSans-Bold.ttf"),
+                        font_size: 14.,
+                        color: Color::GRAY,
+                    },
+                )
+                .attach(tracker),
+        );
 
     commands
         .spawn_bundle(build!(line))
@@ -163,6 +226,8 @@ struct Page1Inspector {
     freq: f64,
     amp: f64,
     phase: f64,
+    /// Which sliders were moved since the variables were last written, in the order above.
+    edited: [bool; 3],
 }
 
 impl Default for Page1Inspector {
@@ -171,6 +236,7 @@ impl Default for Page1Inspector {
             freq: 2.,
             amp: 30.,
             phase: 0.,
+            edited: [false; 3],
         }
     }
 }
@@ -186,51 +252,91 @@ fn update_page1_inspector(
             .show(ctx, |ui| {
                 ui.horizontal(|ui| {
                     ui.label("Frequency");
-                    ui.add(egui::Slider::new(&mut inspector.freq, 1.0..=30.).integer());
+                    if ui
+                        .add(egui::Slider::new(&mut inspector.freq, 1.0..=30.).integer())
+                        .changed()
+                    {
+                        inspector.edited[0] = true;
+                    }
                 });
                 ui.horizontal(|ui| {
                     ui.label("Amplitude");
-                    ui.add(egui::Slider::new(&mut inspector.amp, 0.5..=100.).integer());
+                    if ui
+                        .add(egui::Slider::new(&mut inspector.amp, 0.5..=100.).integer())
+                        .changed()
+                    {
+                        inspector.edited[1] = true;
+                    }
                 });
                 ui.horizontal(|ui| {
                     ui.label("Phase");
-                    ui.add(egui::Slider::new(&mut inspector.phase, 0. ..=(PI * 2.)));
+                    if ui
+                        .add(egui::Slider::new(&mut inspector.phase, 0. ..=(PI * 2.)))
+                        .changed()
+                    {
+                        inspector.edited[2] = true;
+                    }
                 });
             });
     }
 }
 
 fn update_page1_variables_from_gui(
-    inspector: Res<Page1Inspector>,
+    mut inspector: ResMut<Page1Inspector>,
+    mut history: ResMut<EditHistory>,
     mut vars: ParamSet<(
-        Query<(&Group, &mut Variable), With<Freq>>,
-        Query<(&Group, &mut Variable), With<Amp>>,
-        Query<(&Group, &mut Variable), With<Phase>>,
+        Query<(Entity, &Group, &mut Variable), With<Freq>>,
+        Query<(Entity, &Group, &mut Variable), With<Amp>>,
+        Query<(Entity, &Group, &mut Variable), With<Phase>>,
     )>,
     page: Res<State<Page>>,
 ) {
-    if inspector.is_changed() && *page.current() == Page::Simple {
-        // TODO: This is always true?
-        vars.p0()
-            .iter_mut()
-            .filter(|w| w.0 .0 == PAGE1)
-            .next()
-            .unwrap()
-            .1
-            .set_value(inspector.freq);
-        vars.p1()
-            .iter_mut()
-            .filter(|w| w.0 .0 == PAGE1)
-            .next()
-            .unwrap()
-            .1
-            .set_value(inspector.amp);
-        vars.p2()
-            .iter_mut()
-            .filter(|w| w.0 .0 == PAGE1)
-            .next()
-            .unwrap()
-            .1
-            .set_value(inspector.phase);
+    let edited = std::mem::take(&mut inspector.edited);
+    if *page.current() == Page::Simple {
+        if edited[0] {
+            vars.p0()
+                .iter_mut()
+                .filter(|w| w.1 .0 == PAGE1)
+                .for_each(|(entity, _, mut var)| {
+                    history.edit(Page::Simple, entity, &mut var, inspector.freq)
+                });
+        }
+        if edited[1] {
+            vars.p1()
+                .iter_mut()
+                .filter(|w| w.1 .0 == PAGE1)
+                .for_each(|(entity, _, mut var)| {
+                    history.edit(Page::Simple, entity, &mut var, inspector.amp)
+                });
+        }
+        if edited[2] {
+            vars.p2()
+                .iter_mut()
+                .filter(|w| w.1 .0 == PAGE1)
+                .for_each(|(entity, _, mut var)| {
+                    history.edit(Page::Simple, entity, &mut var, inspector.phase)
+                });
+        }
+    }
+}
+
+/// Show values set from outside the inspector, such as by undo and redo.
+fn update_page1_inspector_from_outside(
+    mut inspector: ResMut<Page1Inspector>,
+    mut outside: EventReader<SetFromOutside>,
+    freqs: Query<&Group, With<Freq>>,
+    amps: Query<&Group, With<Amp>>,
+    phases: Query<&Group, With<Phase>>,
+) {
+    for SetFromOutside { variable, value } in outside.iter() {
+        if freqs.get(*variable).map_or(false, |w| w.0 == PAGE1) {
+            inspector.freq = *value;
+        }
+        if amps.get(*variable).map_or(false, |w| w.0 == PAGE1) {
+            inspector.amp = *value;
+        }
+        if phases.get(*variable).map_or(false, |w| w.0 == PAGE1) {
+            inspector.phase = *value;
+        }
     }
 }
@@ -4,17 +4,15 @@ use bevy::prelude::*;
 use bevy_egui::{egui, EguiContext};
 use bevy_prototype_lyon::{prelude::*, shapes::Circle};
 
+use crate::clock::PageClocks;
 use crate::drawing::boundcircle::BoundCircle;
 use crate::drawing::boundline::BoundLine;
 use crate::drawing::boundlocation::BoundLocation;
 use crate::drawing::boundtracker::BoundTracker;
-use crate::variables::lambda::*;
-use crate::variables::{
-    group::Group,
-    variable::{dependent, independent},
-    Variable,
-};
-use crate::{Page, Time, GLOBAL};
+use crate::drivers::{Lfo, Waveform};
+use crate::history::{EditHistory, SetFromOutside};
+use crate::variables::{builder::VarScope, group::Group, Variable};
+use crate::{expr, Headless, Page, GLOBAL};
 
 const UPPER: usize = 2;
 const LOWER: usize = 3;
@@ -30,10 +28,15 @@ pub struct Page2Plugin;
 
 impl Plugin for Page2Plugin {
     fn build(&self, app: &mut App) {
-        app.add_system(update_page2_inspector)
-            .add_system(update_page2_variables_from_gui)
-            .add_startup_system(page2_setup)
+        app.add_startup_system(page2_setup)
             .insert_resource(Page2Inspector::default());
+        if !app.world.contains_resource::<Headless>() {
+            // Show values set from outside before drawing the sliders, and write back after, so
+            // a value set this frame is not overwritten by the slider's old one.
+            app.add_system(update_page2_inspector_from_outside.after("set_from_outside"))
+                .add_system(update_page2_inspector.after(update_page2_inspector_from_outside))
+                .add_system(update_page2_variables_from_gui.after(update_page2_inspector));
+        }
     }
 }
 
@@ -46,54 +49,61 @@ macro_rules! build {
         )
     };
 }
-fn page2_setup(mut commands: Commands) {
-    let global = Group(GLOBAL);
+fn page2_setup(mut commands: Commands, clocks: Res<PageClocks>) {
+    let global_vars = VarScope::new(Group(GLOBAL), Page::Combination);
     let upper = Group(UPPER);
     let lower = Group(LOWER);
-    let time = independent(&mut commands, &global, "time", 0.);
-    let point_rad = independent(&mut commands, &global, "point_rad", 10.);
-    let zero = independent(&mut commands, &global, "0", 0.);
-    let mut frame_maker = |offset: f64, group: &Group| {
-        let phase = independent(&mut commands, group, "phase", 0.);
-        let freq = independent(&mut commands, group, "freq", 2.);
-        let amp = independent(&mut commands, group, "amp", 30.);
-        let circle_x = independent(&mut commands, group, "circle_x", -200.);
-        let shift_y = independent(&mut commands, group, "shift_y", offset);
-        let theta = dependent(
-            &mut commands,
-            group,
-            "theta",
-            Mod(Add(Var(phase), Mul(Var(time), Var(freq))), Num(2. * PI)),
-        );
-        let cos_theta = dependent(
-            &mut commands,
-            group,
-            "cos(theta)",
-            Mul(Var(amp), Cos(Var(theta))),
-        );
-        let sin_theta = dependent(
-            &mut commands,
-            group,
-            "sin(theta)",
-            Mul(Var(amp), Sin(Var(theta))),
-        );
-        let circle_cos = dependent(
-            &mut commands,
-            group,
-            "circle_cos",
-            Add(Var(circle_x), Var(cos_theta)),
-        );
-        let circle_sin = dependent(
-            &mut commands,
-            group,
-            "circle_sin",
-            Add(Var(shift_y), Var(sin_theta)),
-        );
-
-        commands.entity(time).insert(Time);
-        commands.entity(amp).insert(Amp);
-        commands.entity(freq).insert(Freq);
-        commands.entity(phase).insert(Phase);
+    let time = clocks.variable(Page::Combination);
+    // The points swell and shrink slowly, so the eye follows them round their circles.
+    let point_rad = global_vars
+        .independent("point_rad")
+        .value(10.)
+        .tag(Lfo::new(Waveform::Sine, 0.5, 2., 10.))
+        .spawn(&mut commands);
+    let zero = global_vars.independent("0").spawn(&mut commands);
+    let mut frame_maker = |offset: f64, group: Group| {
+        let vars = VarScope::new(group, Page::Combination);
+        let phase = vars
+            .independent("phase")
+            .range(0. ..=(PI * 2.))
+            .tag(Phase)
+            .spawn(&mut commands);
+        let freq = vars
+            .independent("freq")
+            .value(2.)
+            .range(1.0..=30.)
+            .tag(Freq)
+            .spawn(&mut commands);
+        let amp = vars
+            .independent("amp")
+            .value(30.)
+            .range(0.5..=100.)
+            .tag(Amp)
+            .spawn(&mut commands);
+        let circle_x = vars
+            .independent("circle_x")
+            .value(-200.)
+            .spawn(&mut commands);
+        let shift_y = vars
+            .independent("shift_y")
+            .value(offset)
+            .spawn(&mut commands);
+        let theta = vars
+            .dependent("theta", expr!((phase + time * freq) % { 2. * PI }))
+            .wraps(2. * PI)
+            .spawn(&mut commands);
+        let cos_theta = vars
+            .dependent("cos(theta)", expr!(amp * cos(theta)))
+            .spawn(&mut commands);
+        let sin_theta = vars
+            .dependent("sin(theta)", expr!(amp * sin(theta)))
+            .spawn(&mut commands);
+        let circle_cos = vars
+            .dependent("circle_cos", expr!(circle_x + cos_theta))
+            .spawn(&mut commands);
+        let circle_sin = vars
+            .dependent("circle_sin", expr!(shift_y + sin_theta))
+            .spawn(&mut commands);
 
         let circle = Circle::default();
 
@@ -129,16 +139,13 @@ fn page2_setup(mut commands: Commands) {
         return (amp, cos_theta, sin_theta);
     };
 
-    let (upper_amp, upper_cos, upper_sin) = frame_maker(200., &upper);
-    let (lower_amp, lower_cos, lower_sin) = frame_maker(0., &lower);
+    let (upper_amp, upper_cos, upper_sin) = frame_maker(200., upper);
+    let (lower_amp, lower_cos, lower_sin) = frame_maker(0., lower);
 
     let line = PathBuilder::new().build();
-    let sum = dependent(
-        &mut commands,
-        &global,
-        "sum",
-        Add(Add(Var(upper_sin), Var(lower_sin)), Num(-200.)),
-    );
+    let sum = global_vars
+        .dependent("sum", expr!(upper_sin + lower_sin - 200.))
+        .spawn(&mut commands);
     commands
         .spawn_bundle(build!(line))
         .insert(Page::Combination)
@@ -146,43 +153,34 @@ fn page2_setup(mut commands: Commands) {
 
     let circle = Circle::default();
 
-    let sum_center = independent(&mut commands, &global, "lower center", -200.);
+    let sum_center = global_vars
+        .independent("lower center")
+        .value(-200.)
+        .spawn(&mut commands);
     commands
         .spawn_bundle(build!(circle))
         .insert(Page::Combination)
         .insert(BoundCircle::new(lower_amp))
         .insert(BoundLocation::new(sum_center, sum_center));
 
-    let sum_cos = dependent(
-        &mut commands,
-        &global,
-        "sum cos",
-        Add(Num(-200.), Var(lower_cos)),
-    );
-    let sum_sin = dependent(
-        &mut commands,
-        &global,
-        "sum sin",
-        Add(Num(-200.), Var(lower_sin)),
-    );
+    let sum_cos = global_vars
+        .dependent("sum cos", expr!(lower_cos - 200.))
+        .spawn(&mut commands);
+    let sum_sin = global_vars
+        .dependent("sum sin", expr!(lower_sin - 200.))
+        .spawn(&mut commands);
     commands
         .spawn_bundle(build!(circle))
         .insert(Page::Combination)
         .insert(BoundCircle::new(upper_amp))
         .insert(BoundLocation::new(sum_cos, sum_sin));
 
-    let sum_point_x = dependent(
-        &mut commands,
-        &global,
-        "sum_point_x",
-        Add(Var(sum_cos), Var(upper_cos)),
-    );
-    let sum_point_y = dependent(
-        &mut commands,
-        &global,
-        "sum_point_y",
-        Add(Var(sum_sin), Var(upper_sin)),
-    );
+    let sum_point_x = global_vars
+        .dependent("sum_point_x", expr!(sum_cos + upper_cos))
+        .spawn(&mut commands);
+    let sum_point_y = global_vars
+        .dependent("sum_point_y", expr!(sum_sin + upper_sin))
+        .spawn(&mut commands);
     commands
         .spawn_bundle(GeometryBuilder::build_as(
             &circle,
@@ -206,6 +204,8 @@ struct Page2Inspector {
     freq2: f64,
     amp2: f64,
     phase2: f64,
+    /// Which sliders were moved since the variables were last written, in the order above.
+    edited: [bool; 6],
 }
 
 impl Default for Page2Inspector {
@@ -217,6 +217,7 @@ impl Default for Page2Inspector {
             freq2: 2.,
             amp2: 30.,
             phase2: 0.,
+            edited: [false; 6],
         }
     }
 }
@@ -232,85 +233,152 @@ fn update_page2_inspector(
             .show(ctx, |ui| {
                 ui.horizontal(|ui| {
                     ui.label("Frequency");
-                    ui.add(egui::Slider::new(&mut inspector.freq1, 1.0..=30.).integer());
+                    if ui
+                        .add(egui::Slider::new(&mut inspector.freq1, 1.0..=30.).integer())
+                        .changed()
+                    {
+                        inspector.edited[0] = true;
+                    }
                 });
                 ui.horizontal(|ui| {
                     ui.label("Amplitude");
-                    ui.add(egui::Slider::new(&mut inspector.amp1, 0.5..=100.).integer());
+                    if ui
+                        .add(egui::Slider::new(&mut inspector.amp1, 0.5..=100.).integer())
+                        .changed()
+                    {
+                        inspector.edited[1] = true;
+                    }
                 });
                 ui.horizontal(|ui| {
                     ui.label("Phase");
-                    ui.add(egui::Slider::new(&mut inspector.phase1, 0. ..=(PI * 2.)));
+                    if ui
+                        .add(egui::Slider::new(&mut inspector.phase1, 0. ..=(PI * 2.)))
+                        .changed()
+                    {
+                        inspector.edited[2] = true;
+                    }
                 });
                 ui.separator();
                 ui.horizontal(|ui| {
                     ui.label("Frequency");
-                    ui.add(egui::Slider::new(&mut inspector.freq2, 1.0..=30.).integer());
+                    if ui
+                        .add(egui::Slider::new(&mut inspector.freq2, 1.0..=30.).integer())
+                        .changed()
+                    {
+                        inspector.edited[3] = true;
+                    }
                 });
                 ui.horizontal(|ui| {
                     ui.label("Amplitude");
-                    ui.add(egui::Slider::new(&mut inspector.amp2, 0.5..=100.).integer());
+                    if ui
+                        .add(egui::Slider::new(&mut inspector.amp2, 0.5..=100.).integer())
+                        .changed()
+                    {
+                        inspector.edited[4] = true;
+                    }
                 });
                 ui.horizontal(|ui| {
                     ui.label("Phase");
-                    ui.add(egui::Slider::new(&mut inspector.phase2, 0. ..=(PI * 2.)));
+                    if ui
+                        .add(egui::Slider::new(&mut inspector.phase2, 0. ..=(PI * 2.)))
+                        .changed()
+                    {
+                        inspector.edited[5] = true;
+                    }
                 });
             });
     }
 }
 
 fn update_page2_variables_from_gui(
-    inspector: Res<Page2Inspector>,
+    mut inspector: ResMut<Page2Inspector>,
+    mut history: ResMut<EditHistory>,
     mut vars: ParamSet<(
-        Query<(&Group, &mut Variable), With<Freq>>,
-        Query<(&Group, &mut Variable), With<Amp>>,
-        Query<(&Group, &mut Variable), With<Phase>>,
+        Query<(Entity, &Group, &mut Variable), With<Freq>>,
+        Query<(Entity, &Group, &mut Variable), With<Amp>>,
+        Query<(Entity, &Group, &mut Variable), With<Phase>>,
     )>,
     page: Res<State<Page>>,
 ) {
-    if inspector.is_changed() && *page.current() == Page::Combination {
-        // TODO: This is always true?
-        vars.p0()
-            .iter_mut()
-            .filter(|w| w.0 .0 == UPPER)
-            .next()
-            .unwrap()
-            .1
-            .set_value(inspector.freq1);
-        vars.p1()
-            .iter_mut()
-            .filter(|w| w.0 .0 == UPPER)
-            .next()
-            .unwrap()
-            .1
-            .set_value(inspector.amp1);
-        vars.p2()
-            .iter_mut()
-            .filter(|w| w.0 .0 == UPPER)
-            .next()
-            .unwrap()
-            .1
-            .set_value(inspector.phase1);
-        vars.p0()
-            .iter_mut()
-            .filter(|w| w.0 .0 == LOWER)
-            .next()
-            .unwrap()
-            .1
-            .set_value(inspector.freq2);
-        vars.p1()
-            .iter_mut()
-            .filter(|w| w.0 .0 == LOWER)
-            .next()
-            .unwrap()
-            .1
-            .set_value(inspector.amp2);
-        vars.p2()
-            .iter_mut()
-            .filter(|w| w.0 .0 == LOWER)
-            .next()
-            .unwrap()
-            .1
-            .set_value(inspector.phase2);
+    let edited = std::mem::take(&mut inspector.edited);
+    if *page.current() == Page::Combination {
+        if edited[0] {
+            vars.p0()
+                .iter_mut()
+                .filter(|w| w.1 .0 == UPPER)
+                .for_each(|(entity, _, mut var)| {
+                    history.edit(Page::Combination, entity, &mut var, inspector.freq1)
+                });
+        }
+        if edited[1] {
+            vars.p1()
+                .iter_mut()
+                .filter(|w| w.1 .0 == UPPER)
+                .for_each(|(entity, _, mut var)| {
+                    history.edit(Page::Combination, entity, &mut var, inspector.amp1)
+                });
+        }
+        if edited[2] {
+            vars.p2()
+                .iter_mut()
+                .filter(|w| w.1 .0 == UPPER)
+                .for_each(|(entity, _, mut var)| {
+                    history.edit(Page::Combination, entity, &mut var, inspector.phase1)
+                });
+        }
+        if edited[3] {
+            vars.p0()
+                .iter_mut()
+                .filter(|w| w.1 .0 == LOWER)
+                .for_each(|(entity, _, mut var)| {
+                    history.edit(Page::Combination, entity, &mut var, inspector.freq2)
+                });
+        }
+        if edited[4] {
+            vars.p1()
+                .iter_mut()
+                .filter(|w| w.1 .0 == LOWER)
+                .for_each(|(entity, _, mut var)| {
+                    history.edit(Page::Combination, entity, &mut var, inspector.amp2)
+                });
+        }
+        if edited[5] {
+            vars.p2()
+                .iter_mut()
+                .filter(|w| w.1 .0 == LOWER)
+                .for_each(|(entity, _, mut var)| {
+                    history.edit(Page::Combination, entity, &mut var, inspector.phase2)
+                });
+        }
+    }
+}
+
+/// Show values set from outside the inspector, such as by undo and redo.
+fn update_page2_inspector_from_outside(
+    mut inspector: ResMut<Page2Inspector>,
+    mut outside: EventReader<SetFromOutside>,
+    freqs: Query<&Group, With<Freq>>,
+    amps: Query<&Group, With<Amp>>,
+    phases: Query<&Group, With<Phase>>,
+) {
+    for SetFromOutside { variable, value } in outside.iter() {
+        if freqs.get(*variable).map_or(false, |w| w.0 == UPPER) {
+            inspector.freq1 = *value;
+        }
+        if amps.get(*variable).map_or(false, |w| w.0 == UPPER) {
+            inspector.amp1 = *value;
+        }
+        if phases.get(*variable).map_or(false, |w| w.0 == UPPER) {
+            inspector.phase1 = *value;
+        }
+        if freqs.get(*variable).map_or(false, |w| w.0 == LOWER) {
+            inspector.freq2 = *value;
+        }
+        if amps.get(*variable).map_or(false, |w| w.0 == LOWER) {
+            inspector.amp2 = *value;
+        }
+        if phases.get(*variable).map_or(false, |w| w.0 == LOWER) {
+            inspector.phase2 = *value;
+        }
     }
 }
@@ -5,17 +5,15 @@ use bevy_egui::{egui, EguiContext};
 use bevy_prototype_lyon::{prelude::*, shapes::Circle};
 use bevy_turborand::{DelegatedRng, GlobalRng, RngPlugin};
 
+use crate::bookmarks::Hidden;
+use crate::clock::PageClocks;
 use crate::drawing::boundcircle::BoundCircle;
 use crate::drawing::boundline::BoundLine;
 use crate::drawing::boundlocation::BoundLocation;
 use crate::drawing::boundtracker::BoundTracker;
-use crate::variables::lambda::*;
-use crate::variables::{
-    group::Group,
-    variable::{dependent, independent},
-    Variable,
-};
-use crate::{EquationText, Page, Time, GLOBAL};
+use crate::history::{EditHistory, SetFromOutside};
+use crate::variables::{builder::VarScope, events::VariableChanged, group::Group, Variable};
+use crate::{expr, EquationText, Headless, Page};
 const KNOWN: usize = 4;
 const UNKNOWN: usize = 5;
 
@@ -31,8 +29,6 @@ pub struct Page3Plugin;
 impl Plugin for Page3Plugin {
     fn build(&self, app: &mut App) {
         app.add_plugin(RngPlugin::default())
-            .add_system(update_page3_inspector)
-            .add_system(update_page3_variables_from_gui)
             .add_system(game_check)
             .add_system(new_game)
             .add_startup_system(page3_setup)
@@ -41,6 +37,13 @@ impl Plugin for Page3Plugin {
             .add_event::<NewGameEvent>()
             .insert_resource(Page3GameState { win: false })
             .insert_resource(Page3Inspector::default());
+        if !app.world.contains_resource::<Headless>() {
+            // Show values set from outside before drawing the sliders, and write back after, so
+            // a value set this frame is not overwritten by the slider's old one.
+            app.add_system(update_page3_inspector_from_outside.after("set_from_outside"))
+                .add_system(update_page3_inspector.after(update_page3_inspector_from_outside))
+                .add_system(update_page3_variables_from_gui.after(update_page3_inspector));
+        }
     }
 }
 
@@ -54,45 +57,48 @@ macro_rules! build {
     };
 }
 
-fn page3_setup(mut commands: Commands, asset_server: Res<AssetServer>) {
-    let global = Group(GLOBAL);
-    let pagegroup = Group(KNOWN);
-    let time = independent(&mut commands, &global, "time", 0.);
-    let phase = independent(&mut commands, &pagegroup, "phase", 0.);
-    let freq = independent(&mut commands, &pagegroup, "freq", 2.);
-    let amp = independent(&mut commands, &pagegroup, "amp", 30.);
-    let circle_x = independent(&mut commands, &pagegroup, "circle_x", -200.);
-    let point_rad = independent(&mut commands, &pagegroup, "point_rad", 10.);
-    let zero = independent(&mut commands, &pagegroup, "0", 0.);
-    let theta = dependent(
-        &mut commands,
-        &pagegroup,
-        "theta",
-        Mod(Add(Var(phase), Mul(Var(time), Var(freq))), Num(2. * PI)),
-    );
-    let cos_theta = dependent(
-        &mut commands,
-        &pagegroup,
-        "cos(theta)",
-        Mul(Var(amp), Cos(Var(theta))),
-    );
-    let sin_theta = dependent(
-        &mut commands,
-        &pagegroup,
-        "sin(theta)",
-        Mul(Var(amp), Sin(Var(theta))),
-    );
-    let circle_cos = dependent(
-        &mut commands,
-        &pagegroup,
-        "circle_cos",
-        Add(Var(circle_x), Var(cos_theta)),
-    );
-
-    commands.entity(time).insert(Time);
-    commands.entity(amp).insert(Amp);
-    commands.entity(freq).insert(Freq);
-    commands.entity(phase).insert(Phase);
+fn page3_setup(mut commands: Commands, clocks: Res<PageClocks>, asset_server: Res<AssetServer>) {
+    let vars = VarScope::new(Group(KNOWN), Page::Game);
+    let time = clocks.variable(Page::Game);
+    let phase = vars
+        .independent("phase")
+        .range(0. ..=(PI * 2.))
+        .tag(Phase)
+        .spawn(&mut commands);
+    let freq = vars
+        .independent("freq")
+        .value(2.)
+        .range(1.0..=30.)
+        .tag(Freq)
+        .spawn(&mut commands);
+    let amp = vars
+        .independent("amp")
+        .value(30.)
+        .range(0.5..=100.)
+        .tag(Amp)
+        .spawn(&mut commands);
+    let circle_x = vars
+        .independent("circle_x")
+        .value(-200.)
+        .spawn(&mut commands);
+    let point_rad = vars
+        .independent("point_rad")
+        .value(10.)
+        .spawn(&mut commands);
+    let zero = vars.independent("0").spawn(&mut commands);
+    let theta = vars
+        .dependent("theta", expr!((phase + time * freq) % { 2. * PI }))
+        .wraps(2. * PI)
+        .spawn(&mut commands);
+    let cos_theta = vars
+        .dependent("cos(theta)", expr!(amp * cos(theta)))
+        .spawn(&mut commands);
+    let sin_theta = vars
+        .dependent("sin(theta)", expr!(amp * sin(theta)))
+        .spawn(&mut commands);
+    let circle_cos = vars
+        .dependent("circle_cos", expr!(circle_x + cos_theta))
+        .spawn(&mut commands);
 
     let circle = Circle::default();
 
@@ -166,46 +172,56 @@ fn page3_setup(mut commands: Commands, asset_server: Res<AssetServer>) {
         });
 }
 
-fn page3_invisible_setup(mut commands: Commands) {
-    let global = Group(GLOBAL);
-    let pagegroup = Group(UNKNOWN);
-    let time = independent(&mut commands, &global, "time", 0.);
-    let phase = independent(&mut commands, &pagegroup, "phase", 1.2);
-    let freq = independent(&mut commands, &pagegroup, "freq", 3.);
-    let amp = independent(&mut commands, &pagegroup, "amp", 45.);
-    let circle_x = independent(&mut commands, &pagegroup, "circle_x", -200.);
-    let shift_y = independent(&mut commands, &pagegroup, "shift_y", -200.);
-    let point_rad = independent(&mut commands, &pagegroup, "point_rad", 10.);
-    let zero = independent(&mut commands, &pagegroup, "0", 0.);
-    let theta = dependent(
-        &mut commands,
-        &pagegroup,
-        "theta",
-        Mod(Add(Var(phase), Mul(Var(time), Var(freq))), Num(2. * PI)),
-    );
-    let cos_theta = dependent(
-        &mut commands,
-        &pagegroup,
-        "cos(theta)",
-        Mul(Var(amp), Cos(Var(theta))),
-    );
-    let sin_theta = dependent(
-        &mut commands,
-        &pagegroup,
-        "sin(theta)",
-        Add(Var(shift_y), Mul(Var(amp), Sin(Var(theta)))),
-    );
-    let circle_cos = dependent(
-        &mut commands,
-        &pagegroup,
-        "circle_cos",
-        Add(Var(circle_x), Var(cos_theta)),
-    );
-
-    commands.entity(time).insert(Time);
-    commands.entity(amp).insert(Amp);
-    commands.entity(freq).insert(Freq);
-    commands.entity(phase).insert(Phase);
+fn page3_invisible_setup(mut commands: Commands, clocks: Res<PageClocks>) {
+    let vars = VarScope::new(Group(UNKNOWN), Page::Game);
+    let time = clocks.variable(Page::Game);
+    let phase = vars
+        .independent("phase")
+        .value(1.2)
+        .range(0. ..=(PI * 2.))
+        .tag(Phase)
+        .tag(Hidden)
+        .spawn(&mut commands);
+    let freq = vars
+        .independent("freq")
+        .value(3.)
+        .range(1.0..=30.)
+        .tag(Freq)
+        .tag(Hidden)
+        .spawn(&mut commands);
+    let amp = vars
+        .independent("amp")
+        .value(45.)
+        .range(0.5..=100.)
+        .tag(Amp)
+        .tag(Hidden)
+        .spawn(&mut commands);
+    let circle_x = vars
+        .independent("circle_x")
+        .value(-200.)
+        .spawn(&mut commands);
+    let shift_y = vars
+        .independent("shift_y")
+        .value(-200.)
+        .spawn(&mut commands);
+    let point_rad = vars
+        .independent("point_rad")
+        .value(10.)
+        .spawn(&mut commands);
+    let zero = vars.independent("0").spawn(&mut commands);
+    let theta = vars
+        .dependent("theta", expr!((phase + time * freq) % { 2. * PI }))
+        .wraps(2. * PI)
+        .spawn(&mut commands);
+    let cos_theta = vars
+        .dependent("cos(theta)", expr!(amp * cos(theta)))
+        .spawn(&mut commands);
+    let sin_theta = vars
+        .dependent("sin(theta)", expr!(shift_y + amp * sin(theta)))
+        .spawn(&mut commands);
+    let circle_cos = vars
+        .dependent("circle_cos", expr!(circle_x + cos_theta))
+        .spawn(&mut commands);
 
     let circle = Circle::default();
 
@@ -244,6 +260,8 @@ struct Page3Inspector {
     freq: f64,
     amp: f64,
     phase: f64,
+    /// Which sliders were moved since the variables were last written, in the order above.
+    edited: [bool; 3],
 }
 
 #[derive(Debug)]
@@ -259,6 +277,7 @@ impl Default for Page3Inspector {
             freq: 2.,
             amp: 30.,
             phase: 0.,
+            edited: [false; 3],
         }
     }
 }
@@ -277,15 +296,30 @@ fn update_page3_inspector(
             .show(ctx, |ui| {
                 ui.horizontal(|ui| {
                     ui.label("Frequency");
-                    ui.add(egui::Slider::new(&mut inspector.freq, 1.0..=30.).integer());
+                    if ui
+                        .add(egui::Slider::new(&mut inspector.freq, 1.0..=30.).integer())
+                        .changed()
+                    {
+                        inspector.edited[0] = true;
+                    }
                 });
                 ui.horizontal(|ui| {
                     ui.label("Amplitude");
-                    ui.add(egui::Slider::new(&mut inspector.amp, 0.5..=100.).integer());
+                    if ui
+                        .add(egui::Slider::new(&mut inspector.amp, 0.5..=100.).integer())
+                        .changed()
+                    {
+                        inspector.edited[1] = true;
+                    }
                 });
                 ui.horizontal(|ui| {
                     ui.label("Phase");
-                    ui.add(egui::Slider::new(&mut inspector.phase, 0. ..=(PI * 2.)));
+                    if ui
+                        .add(egui::Slider::new(&mut inspector.phase, 0. ..=(PI * 2.)))
+                        .changed()
+                    {
+                        inspector.edited[2] = true;
+                    }
                 });
                 if game.win {
                     ui.horizontal(|ui| {
@@ -338,37 +372,62 @@ fn new_game(
 }
 
 fn update_page3_variables_from_gui(
-    inspector: Res<Page3Inspector>,
+    mut inspector: ResMut<Page3Inspector>,
+    mut history: ResMut<EditHistory>,
     mut vars: ParamSet<(
-        Query<(&Group, &mut Variable), With<Freq>>,
-        Query<(&Group, &mut Variable), With<Amp>>,
-        Query<(&Group, &mut Variable), With<Phase>>,
+        Query<(Entity, &Group, &mut Variable), With<Freq>>,
+        Query<(Entity, &Group, &mut Variable), With<Amp>>,
+        Query<(Entity, &Group, &mut Variable), With<Phase>>,
     )>,
     page: Res<State<Page>>,
 ) {
-    if inspector.is_changed() && *page.current() == Page::Game {
-        // TODO: This is always true?
-        vars.p0()
-            .iter_mut()
-            .filter(|w| w.0 .0 == KNOWN)
-            .next()
-            .unwrap()
-            .1
-            .set_value(inspector.freq);
-        vars.p1()
-            .iter_mut()
-            .filter(|w| w.0 .0 == KNOWN)
-            .next()
-            .unwrap()
-            .1
-            .set_value(inspector.amp);
-        vars.p2()
-            .iter_mut()
-            .filter(|w| w.0 .0 == KNOWN)
-            .next()
-            .unwrap()
-            .1
-            .set_value(inspector.phase);
+    let edited = std::mem::take(&mut inspector.edited);
+    if *page.current() == Page::Game {
+        if edited[0] {
+            vars.p0()
+                .iter_mut()
+                .filter(|w| w.1 .0 == KNOWN)
+                .for_each(|(entity, _, mut var)| {
+                    history.edit(Page::Game, entity, &mut var, inspector.freq)
+                });
+        }
+        if edited[1] {
+            vars.p1()
+                .iter_mut()
+                .filter(|w| w.1 .0 == KNOWN)
+                .for_each(|(entity, _, mut var)| {
+                    history.edit(Page::Game, entity, &mut var, inspector.amp)
+                });
+        }
+        if edited[2] {
+            vars.p2()
+                .iter_mut()
+                .filter(|w| w.1 .0 == KNOWN)
+                .for_each(|(entity, _, mut var)| {
+                    history.edit(Page::Game, entity, &mut var, inspector.phase)
+                });
+        }
+    }
+}
+
+/// Show values set from outside the inspector, such as by undo and redo.
+fn update_page3_inspector_from_outside(
+    mut inspector: ResMut<Page3Inspector>,
+    mut outside: EventReader<SetFromOutside>,
+    freqs: Query<&Group, With<Freq>>,
+    amps: Query<&Group, With<Amp>>,
+    phases: Query<&Group, With<Phase>>,
+) {
+    for SetFromOutside { variable, value } in outside.iter() {
+        if freqs.get(*variable).map_or(false, |w| w.0 == KNOWN) {
+            inspector.freq = *value;
+        }
+        if amps.get(*variable).map_or(false, |w| w.0 == KNOWN) {
+            inspector.amp = *value;
+        }
+        if phases.get(*variable).map_or(false, |w| w.0 == KNOWN) {
+            inspector.phase = *value;
+        }
     }
 }
 
@@ -379,9 +438,16 @@ fn game_check(
         Query<(&Group, &mut Variable), With<Amp>>,
         Query<(&Group, &mut Variable), With<Phase>>,
     )>,
+    guesses: Query<(), Or<(With<Freq>, With<Amp>, With<Phase>)>>,
+    mut changes: EventReader<VariableChanged>,
     page: Res<State<Page>>,
 ) {
-    if *page.current() == Page::Game {
+    let guess_changed = changes
+        .iter()
+        .filter(|w| guesses.get(w.entity).is_ok())
+        .count()
+        > 0;
+    if guess_changed && *page.current() == Page::Game {
         let get = |vars: &mut ParamSet<(
             Query<(&Group, &mut Variable), With<Freq>>,
             Query<(&Group, &mut Variable), With<Amp>>,
@@ -5,14 +5,10 @@ use bevy_egui::{egui, EguiContext};
 use bevy_prototype_lyon::prelude::*;
 use bevy_turborand::{DelegatedRng, GlobalRng};
 
+use crate::clock::PageClocks;
 use crate::drawing::boundtracker::BoundTracker;
-use crate::variables::lambda::*;
-use crate::variables::{
-    group::Group,
-    variable::{dependent, independent},
-    Variable,
-};
-use crate::{Page, Time, GLOBAL};
+use crate::variables::{builder::VarScope, group::Group, SimulationStage, Variable};
+use crate::{expr, Headless, Page, GLOBAL};
 
 #[derive(Component)]
 struct Freq;
@@ -27,21 +23,28 @@ struct SumSin;
 struct SinOutput;
 #[derive(Component)]
 struct Offset;
-struct NewRowEvent;
+pub(crate) struct NewRowEvent;
 struct DeleteRowEvent(usize);
 
 pub struct Page4Plugin;
 
 impl Plugin for Page4Plugin {
     fn build(&self, app: &mut App) {
-        app.add_system(update_page4_inspector)
-            .add_startup_system(page4_setup)
+        app.add_startup_system(page4_setup)
             .add_system(new_row)
             .add_system(delete_row)
-            .add_system(update_sum.after("variable_recalculation").before("drawing"))
+            .add_system_to_stage(
+                SimulationStage,
+                update_sum
+                    .after("variable_recalculation")
+                    .before("sampling"),
+            )
             .add_event::<NewRowEvent>()
             .add_event::<DeleteRowEvent>()
             .insert_resource(Page4Inspector::default());
+        if !app.world.contains_resource::<Headless>() {
+            app.add_system(update_page4_inspector);
+        }
     }
 }
 
@@ -56,18 +59,15 @@ macro_rules! build {
 }
 
 fn page4_setup(mut commands: Commands) {
-    let global = Group(GLOBAL);
-    // let time = independent(&mut commands, &global, "time", 0.);
-
+    let global_vars = VarScope::new(Group(GLOBAL), Page::Fourier);
     let line = PathBuilder::new().build();
-    let sum = independent(&mut commands, &global, "sum", 0.);
-    commands.entity(sum).insert(SumSin);
-    let sum_offset = dependent(
-        &mut commands,
-        &global,
-        "sum_offset",
-        Add(Num(300.), Var(sum)),
-    );
+    let sum = global_vars
+        .independent("sum")
+        .tag(SumSin)
+        .spawn(&mut commands);
+    let sum_offset = global_vars
+        .dependent("sum_offset", expr!(300. + sum))
+        .spawn(&mut commands);
     commands
         .spawn_bundle(build!(line))
         .insert(Page::Fourier)
@@ -116,64 +116,65 @@ fn new_row(
     mut queries: ParamSet<(
         Query<&Variable, With<SumSin>>,
         Query<&Variable, With<Offset>>,
-        Query<Entity, With<Time>>,
     )>,
+    clocks: Res<PageClocks>,
     groups: Query<&Group>,
     mut events: EventReader<NewRowEvent>,
     mut rng: ResMut<GlobalRng>,
     mut inspector: ResMut<Page4Inspector>,
 ) {
     let mut group_id = 8;
-    while groups.iter().any(|w| w.0 == group_id) {
-        group_id += 1;
-    }
+    // Rows spawned earlier in this loop are not in the queries until the commands are applied.
+    let mut new_offsets = Vec::new();
     for _event in events.iter() {
+        while groups.iter().any(|w| w.0 == group_id) || inspector.entities.contains(&group_id) {
+            group_id += 1;
+        }
         let mut offset = 200.;
         while queries
             .p1()
             .iter()
-            .filter(|w| w.value() - offset < 1.)
-            .count()
-            > 0
+            .map(|w| w.value())
+            .chain(new_offsets.iter().copied())
+            .any(|w| w - offset < 1.)
         {
             offset -= 75.;
         }
-        let group = &Group(group_id);
-        let phase = independent(&mut commands, group, "phase", rng.i16(1..=200) as f64 / 10.);
-        let freq = independent(&mut commands, group, "freq", rng.i16(1..=90) as f64 / 3.);
-        let amp = independent(&mut commands, group, "amp", rng.i16(5..=25) as f64);
-        let shift_y = independent(&mut commands, group, "shift_y", offset);
-        let theta = dependent(
-            &mut commands,
-            group,
-            "theta",
-            Mod(
-                Add(
-                    Var(phase),
-                    Mul(Var(queries.p2().iter().next().unwrap()), Var(freq)),
-                ),
-                Num(2. * PI),
-            ),
-        );
-        let sin_theta = dependent(
-            &mut commands,
-            group,
-            "sin(theta)",
-            Mul(Var(amp), Sin(Var(theta))),
-        );
-        let circle_sin = dependent(
-            &mut commands,
-            group,
-            "circle_sin",
-            Add(Var(shift_y), Var(sin_theta)),
-        );
-
-        // commands.entity(time).insert(Time);
-        commands.entity(amp).insert(Amp);
-        commands.entity(freq).insert(Freq);
-        commands.entity(phase).insert(Phase);
-        commands.entity(sin_theta).insert(SinOutput);
-        commands.entity(shift_y).insert(Offset);
+        new_offsets.push(offset);
+        let group = Group(group_id);
+        let vars = VarScope::new(group, Page::Fourier);
+        let time = clocks.variable(Page::Fourier);
+        let phase = vars
+            .independent("phase")
+            .value(rng.i16(1..=200) as f64 / 10.)
+            .tag(Phase)
+            .spawn(&mut commands);
+        let freq = vars
+            .independent("freq")
+            .value(rng.i16(1..=90) as f64 / 3.)
+            .tag(Freq)
+            .spawn(&mut commands);
+        let amp = vars
+            .independent("amp")
+            .value(rng.i16(5..=25) as f64)
+            .tag(Amp)
+            .spawn(&mut commands);
+        let shift_y = vars
+            .independent("shift_y")
+            .value(offset)
+            .tag(Offset)
+            .spawn(&mut commands);
+        let theta = vars
+            .dependent("theta", expr!((phase + time * freq) % { 2. * PI }))
+            .wraps(2. * PI)
+            .spawn(&mut commands);
+        let sin_theta = vars
+            .dependent("sin(theta)", expr!(amp * sin(theta)))
+            .tag(SinOutput)
+            .spawn(&mut commands);
+        let circle_sin = vars
+            .dependent("circle_sin", expr!(shift_y + sin_theta))
+            .spawn(&mut commands);
 
         let path_builder = PathBuilder::new();
         let line = path_builder.build();
@@ -181,7 +182,7 @@ fn new_row(
         commands
             .spawn_bundle(build!(line))
             .insert(Page::Fourier)
-            .insert(group.clone())
+            .insert(group)
             .insert(BoundTracker::new(circle_sin, 300));
         inspector.entities.push(group_id);
     }
@@ -0,0 +1,249 @@
+//! Records every variable on every simulation step so the user can scrub back through recent history.
+
+use std::collections::VecDeque;
+
+use bevy::ecs::query::AnyOf;
+use bevy::prelude::*;
+use bevy::time::FixedTimesteps;
+use bevy::utils::HashMap;
+use bevy_egui::{egui, EguiContext};
+use strum::IntoEnumIterator;
+
+use crate::animation::{Animation, AnimationState};
+use crate::clock::{PageClocks, SimulationClock};
+use crate::drawing::boundtracker::{BoundTracker, TrackerState};
+use crate::drawing::boundtrail::BoundTrail;
+use crate::drivers::{DriverState, Lfo, Noise, RandomWalk, SampleAndHold};
+use crate::history::SetFromOutside;
+use crate::variables::binding::PreviousValues;
+use crate::variables::{Independent, SimulationStage, Variable, SIMULATION_TIMESTEP};
+use crate::Page;
+
+/// Adds the [`Recording`], the systems that fill and replay it and the timeline scrubber.
+pub struct RecordingPlugin {
+    /// How many simulation steps to keep.
+    pub capacity: usize,
+}
+
+impl Default for RecordingPlugin {
+    fn default() -> Self {
+        Self { capacity: 3600 }
+    }
+}
+
+impl Plugin for RecordingPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(Recording::new(self.capacity))
+            .add_system(timeline)
+            .add_system_set_to_stage(
+                SimulationStage,
+                SystemSet::new()
+                    .label("record")
+                    .after("sampling")
+                    .with_system(record_frame)
+                    .with_system(replay_frame.after(record_frame)),
+            );
+    }
+}
+
+/// Every variable's value, and the state of every driver, animation and tracker, at the end of
+/// one simulation step.
+pub struct Frame {
+    pub elapsed: f64,
+    pub values: HashMap<Entity, f64>,
+    pub drivers: Vec<(Entity, DriverState)>,
+    pub animations: Vec<(Entity, AnimationState)>,
+    pub trackers: HashMap<Entity, TrackerState>,
+}
+
+/// A ring buffer of the most recent simulation steps.
+pub struct Recording {
+    frames: VecDeque<Frame>,
+    capacity: usize,
+    /// The frame being shown instead of the live simulation, if the user is scrubbing.
+    cursor: Option<usize>,
+    /// The frame last sent to the inspectors, so they are only told when the cursor moves.
+    shown: Option<usize>,
+}
+
+impl Recording {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            frames: VecDeque::with_capacity(capacity),
+            capacity,
+            cursor: None,
+            shown: None,
+        }
+    }
+
+    pub fn frames(&self) -> &VecDeque<Frame> {
+        &self.frames
+    }
+
+    pub fn cursor(&self) -> Option<usize> {
+        self.cursor
+    }
+
+    pub fn is_scrubbing(&self) -> bool {
+        self.cursor.is_some()
+    }
+
+    fn push(&mut self, frame: Frame) {
+        if self.frames.len() == self.capacity {
+            self.frames.pop_front();
+        }
+        self.frames.push_back(frame);
+    }
+
+    /// Move the cursor `frames` frames forward, or back if negative, staying within the recording.
+    fn step_cursor(&mut self, frames: isize) {
+        if let Some(cursor) = self.cursor {
+            let last = self.frames.len() as isize - 1;
+            self.cursor = Some((cursor as isize + frames).clamp(0, last.max(0)) as usize);
+        }
+    }
+
+    /// Drop every frame after the cursor and go back to recording, so the simulation carries on
+    /// from the frame that was showing.
+    fn branch(&mut self) {
+        if let Some(cursor) = self.cursor.take() {
+            self.frames.truncate(cursor + 1);
+        }
+        self.shown = None;
+    }
+}
+
+/// Store this step's values, or branch off from the scrubbed frame if the clock was started again.
+///
+/// While scrubbing, stepping the clock moves the cursor by as many frames as the step covers.
+fn record_frame(
+    mut recording: ResMut<Recording>,
+    clock: Res<SimulationClock>,
+    timesteps: Res<FixedTimesteps>,
+    var_query: Query<(Entity, &Variable)>,
+    driver_query: Query<(Entity, AnyOf<(&Lfo, &Noise, &RandomWalk, &SampleAndHold)>)>,
+    animation_query: Query<(Entity, &Animation)>,
+    tracker_query: Query<(Entity, &BoundTracker)>,
+) {
+    if recording.is_scrubbing() {
+        if clock.paused {
+            if clock.delta() != 0. {
+                let frame = timesteps.get(SIMULATION_TIMESTEP).unwrap().step() * clock.speed;
+                let frames = (clock.delta() / frame).round() as isize;
+                recording.step_cursor(if frames == 0 {
+                    clock.delta().signum() as isize
+                } else {
+                    frames
+                });
+            }
+            return;
+        }
+        recording.branch();
+    }
+    if clock.delta() == 0. {
+        return;
+    }
+    recording.push(Frame {
+        elapsed: clock.elapsed(),
+        values: var_query.iter().map(|(e, v)| (e, v.value())).collect(),
+        drivers: driver_query
+            .iter()
+            .flat_map(|(e, drivers)| DriverState::save(drivers).into_iter().map(move |w| (e, w)))
+            .collect(),
+        animations: animation_query
+            .iter()
+            .map(|(e, a)| (e, a.state()))
+            .collect(),
+        trackers: tracker_query.iter().map(|(e, t)| (e, t.state())).collect(),
+    });
+}
+
+/// While scrubbing, put every variable, clock, driver, animation and tracker back the way it was
+/// at the cursor. Trails are emptied when the cursor moves, and grow again from there.
+fn replay_frame(
+    mut recording: ResMut<Recording>,
+    mut clock: ResMut<SimulationClock>,
+    mut clocks: ResMut<PageClocks>,
+    mut previous: ResMut<PreviousValues>,
+    mut var_query: Query<(Entity, &mut Variable, Option<&Independent>)>,
+    mut tracker_query: Query<(Entity, &mut BoundTracker)>,
+    mut trail_query: Query<&mut BoundTrail>,
+    mut driver_query: Query<AnyOf<(&mut Lfo, &mut Noise, &mut RandomWalk, &mut SampleAndHold)>>,
+    mut animation_query: Query<&mut Animation>,
+    mut outside: EventWriter<SetFromOutside>,
+) {
+    let cursor = match recording.cursor {
+        Some(cursor) => cursor,
+        None => return,
+    };
+    let frame = &recording.frames[cursor];
+    let moved = recording.shown != Some(cursor);
+
+    clock.seek(frame.elapsed);
+    for page in Page::iter() {
+        let page_clock = clocks.get_mut(page);
+        if let Some(elapsed) = frame.values.get(&page_clock.variable()) {
+            page_clock.seek(*elapsed);
+        }
+    }
+    for (entity, mut var, independent) in var_query.iter_mut() {
+        if let Some(value) = frame.values.get(&entity) {
+            var.set_value(*value);
+            previous.0.insert(entity, *value);
+            if moved && independent.is_some() {
+                outside.send(SetFromOutside {
+                    variable: entity,
+                    value: *value,
+                });
+            }
+        }
+    }
+    for &(entity, state) in frame.drivers.iter() {
+        if let Ok(drivers) = driver_query.get_mut(entity) {
+            state.restore(drivers);
+        }
+    }
+    for &(entity, state) in frame.animations.iter() {
+        if let Ok(mut animation) = animation_query.get_mut(entity) {
+            animation.restore(state);
+        }
+    }
+    if moved {
+        for mut trail in trail_query.iter_mut() {
+            trail.clear();
+        }
+        for (entity, mut tracker) in tracker_query.iter_mut() {
+            let states = recording.frames.range(..=cursor).rev();
+            tracker.restore(states.map_while(|w| w.trackers.get(&entity).copied()));
+        }
+    }
+    recording.shown = Some(cursor);
+}
+
+/// A slider over the recorded steps. Dragging it pauses the clock and shows the chosen step.
+fn timeline(
+    mut recording: ResMut<Recording>,
+    mut clock: ResMut<SimulationClock>,
+    mut egui_context: ResMut<EguiContext>,
+) {
+    if recording.frames.is_empty() {
+        return;
+    }
+    let last = recording.frames.len() - 1;
+    let mut position = recording.cursor.unwrap_or(last);
+    egui::Window::new("Timeline")
+        .anchor(egui::Align2::CENTER_BOTTOM, [0.0, -10.0])
+        .show(egui_context.ctx_mut(), |ui| {
+            ui.horizontal(|ui| {
+                let slider = egui::Slider::new(&mut position, 0..=last).show_value(false);
+                if ui.add(slider).changed() {
+                    clock.paused = true;
+                    recording.cursor = Some(position);
+                }
+                ui.label(format!("t = {:.2}", recording.frames[position].elapsed));
+                if recording.is_scrubbing() && ui.button("Resume").clicked() {
+                    clock.paused = false;
+                }
+            });
+        });
+}
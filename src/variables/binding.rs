@@ -1,12 +1,31 @@
 use bevy::prelude::*;
+use bevy::time::FixedTimesteps;
+use bevy::utils::HashMap;
 
-use super::variable::Variable;
+use super::{
+    variable::{Modulus, Variable},
+    SIMULATION_TIMESTEP,
+};
 
 pub trait Bound {
     fn get_bindings(&self) -> Vec<Entity>;
     fn set_bindings(&mut self, bindings: Vec<f64>);
 }
 
+/// The value of every variable at the end of the previous simulation step.
+#[derive(Default)]
+pub struct PreviousValues(pub HashMap<Entity, f64>);
+
+pub fn store_previous_values(
+    mut previous: ResMut<PreviousValues>,
+    var_query: Query<(Entity, &Variable)>,
+) {
+    previous.0.clear();
+    previous
+        .0
+        .extend(var_query.iter().map(|(e, v)| (e, v.value())));
+}
+
 pub fn update_bindings<T: Bound + Component>(
     mut binding_query: Query<&mut T>,
     var_query: Query<&Variable>,
@@ -20,3 +39,35 @@ pub fn update_bindings<T: Bound + Component>(
         bound.set_bindings(values);
     }
 }
+
+/// Bind to values part way between the last two simulation steps, according to how far the
+/// current frame is past the latest one, so movement stays smooth at any frame rate.
+///
+/// A variable with a [`Modulus`] that moved more than half of it is taken to have wrapped
+/// around, and is bound to its new value straight away.
+pub fn interpolate_bindings<T: Bound + Component>(
+    mut binding_query: Query<&mut T>,
+    var_query: Query<(&Variable, Option<&Modulus>)>,
+    previous: Res<PreviousValues>,
+    timesteps: Res<FixedTimesteps>,
+) {
+    let alpha = timesteps
+        .get(SIMULATION_TIMESTEP)
+        .map_or(1., |w| w.overstep_percentage());
+    for mut bound in binding_query.iter_mut() {
+        let bindings = bound.get_bindings();
+        let values: Vec<f64> = bindings
+            .iter()
+            .map(|w| {
+                let (var, modulus) = var_query.get(*w).unwrap();
+                let current = var.value();
+                let old = previous.0.get(w).copied().unwrap_or(current);
+                match modulus {
+                    Some(Modulus(m)) if (current - old).abs() > m.abs() / 2. => current,
+                    _ => old + (current - old) * alpha,
+                }
+            })
+            .collect();
+        bound.set_bindings(values);
+    }
+}
@@ -0,0 +1,202 @@
+use std::borrow::Cow;
+use std::ops::RangeInclusive;
+use std::sync::Arc;
+
+use bevy::ecs::system::Command;
+use bevy::prelude::*;
+
+use super::{
+    group::Group,
+    lambda::{Lam, Var},
+    variable::{Dependent, Independent, Modulus, ValueRange, Variable, VariableBundle},
+};
+
+/// Inserts an extra component on a freshly spawned variable.
+type Tag = Box<dyn FnOnce(&mut World, Entity) + Send + Sync>;
+
+/// Collects everything needed to spawn a variable, then spawns it in one go.
+///
+/// ```ignore
+/// let amp = Var::independent("amp")
+///     .group(pagegroup)
+///     .value(30.)
+///     .range(0.5..=100.)
+///     .tag(Amp)
+///     .spawn(&mut commands);
+/// ```
+pub struct VariableBuilder {
+    name: Cow<'static, str>,
+    variable: Variable,
+    group: Option<Group>,
+    range: Option<RangeInclusive<f64>>,
+    modulus: Option<f64>,
+    tags: Vec<Tag>,
+}
+
+impl Var {
+    /// Start building an independent variable, which holds a value of zero until told otherwise.
+    pub fn independent(name: impl Into<Cow<'static, str>>) -> VariableBuilder {
+        VariableBuilder::new(name.into(), Variable::Independent { value: 0. })
+    }
+
+    /// Start building a dependent variable calculated from `equation`.
+    pub fn dependent<T: Lam + 'static>(
+        name: impl Into<Cow<'static, str>>,
+        equation: T,
+    ) -> VariableBuilder {
+        VariableBuilder::new(
+            name.into(),
+            Variable::Dependent {
+                value: 0.,
+                recalculated: false,
+                equation: Arc::new(equation),
+            },
+        )
+    }
+}
+
+/// Starts builders that share a group and a tag, such as every variable in one group of a page,
+/// so each one need not repeat them.
+///
+/// ```ignore
+/// let vars = VarScope::new(Group(PAGE1), Page::Simple);
+/// let amp = vars.independent("amp").value(30.).spawn(&mut commands);
+/// ```
+#[derive(Clone, Copy)]
+pub struct VarScope<T> {
+    group: Group,
+    tag: T,
+}
+
+impl<T: Component + Clone> VarScope<T> {
+    pub fn new(group: Group, tag: T) -> Self {
+        Self { group, tag }
+    }
+
+    pub fn independent(&self, name: impl Into<Cow<'static, str>>) -> VariableBuilder {
+        Var::independent(name)
+            .group(self.group)
+            .tag(self.tag.clone())
+    }
+
+    pub fn dependent<L: Lam + 'static>(
+        &self,
+        name: impl Into<Cow<'static, str>>,
+        equation: L,
+    ) -> VariableBuilder {
+        Var::dependent(name, equation)
+            .group(self.group)
+            .tag(self.tag.clone())
+    }
+}
+
+impl VariableBuilder {
+    fn new(name: Cow<'static, str>, variable: Variable) -> Self {
+        Self {
+            name,
+            variable,
+            group: None,
+            range: None,
+            modulus: None,
+            tags: Vec::new(),
+        }
+    }
+
+    pub fn group(mut self, group: Group) -> Self {
+        self.group = Some(group);
+        self
+    }
+
+    /// Set the starting value. Dependent variables overwrite it on their first evaluation.
+    pub fn value(mut self, value: f64) -> Self {
+        self.variable.set_value(value);
+        self
+    }
+
+    pub fn range(mut self, range: RangeInclusive<f64>) -> Self {
+        self.range = Some(range);
+        self
+    }
+
+    /// Mark the variable as wrapping around every `modulus`, like an angle.
+    pub fn wraps(mut self, modulus: f64) -> Self {
+        self.modulus = Some(modulus);
+        self
+    }
+
+    /// Add a component to the variable, usually a marker used to find it again in queries.
+    pub fn tag<C: Component>(mut self, tag: C) -> Self {
+        self.tags.push(Box::new(move |world, entity| {
+            world.entity_mut(entity).insert(tag);
+        }));
+        self
+    }
+
+    pub fn spawn(self, spawner: &mut impl VariableSpawner) -> Entity {
+        spawner.spawn_variable(self)
+    }
+
+    fn insert(self, world: &mut World, entity: Entity) {
+        let mut entity_mut = world.entity_mut(entity);
+        match self.variable {
+            Variable::Independent { .. } => entity_mut.insert(Independent),
+            Variable::Dependent { .. } => entity_mut.insert(Dependent),
+        };
+        entity_mut.insert_bundle(VariableBundle {
+            variable: self.variable,
+            name: Name::new(self.name),
+        });
+        if let Some(group) = self.group {
+            entity_mut.insert(group);
+        }
+        if let Some(range) = self.range {
+            entity_mut.insert(ValueRange(range));
+        }
+        if let Some(modulus) = self.modulus {
+            entity_mut.insert(Modulus(modulus));
+        }
+        for tag in self.tags {
+            tag(world, entity);
+        }
+    }
+}
+
+/// Anything a [`VariableBuilder`] can spawn its variable with.
+pub trait VariableSpawner {
+    fn spawn_variable(&mut self, builder: VariableBuilder) -> Entity;
+}
+
+impl VariableSpawner for World {
+    fn spawn_variable(&mut self, builder: VariableBuilder) -> Entity {
+        let entity = self.spawn().id();
+        builder.insert(self, entity);
+        entity
+    }
+}
+
+impl VariableSpawner for Commands<'_, '_> {
+    fn spawn_variable(&mut self, builder: VariableBuilder) -> Entity {
+        let entity = self.spawn().id();
+        self.add(InsertVariable { entity, builder });
+        entity
+    }
+}
+
+impl VariableSpawner for ChildBuilder<'_, '_, '_> {
+    fn spawn_variable(&mut self, builder: VariableBuilder) -> Entity {
+        let entity = self.spawn().id();
+        self.add_command(InsertVariable { entity, builder });
+        entity
+    }
+}
+
+struct InsertVariable {
+    entity: Entity,
+    builder: VariableBuilder,
+}
+
+impl Command for InsertVariable {
+    fn write(self, world: &mut World) {
+        self.builder.insert(world, self.entity);
+    }
+}
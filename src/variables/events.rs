@@ -0,0 +1,136 @@
+use std::sync::Arc;
+
+use bevy::prelude::*;
+use bevy::utils::HashMap;
+
+use super::{
+    lambda::{Context, Lam},
+    variable::Variable,
+};
+
+/// Sent once per cycle for every variable whose value differs from the previous cycle.
+pub struct VariableChanged {
+    pub entity: Entity,
+    pub old: f64,
+    pub new: f64,
+}
+
+/// Sent when the condition of the [`Trigger`] on `trigger` is met.
+pub struct Triggered {
+    pub trigger: Entity,
+}
+
+/// Which way a value has to pass through a threshold.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Direction {
+    Rising,
+    Falling,
+    Either,
+}
+
+#[derive(Clone)]
+pub enum Condition {
+    /// The variable passes through `threshold` in `direction`.
+    Crosses {
+        variable: Entity,
+        threshold: f64,
+        direction: Direction,
+    },
+    /// The equation goes from zero or negative to positive.
+    Becomes(Arc<dyn Lam>),
+}
+
+/// Sends a [`Triggered`] event every time its condition is met.
+#[derive(Component, Clone)]
+pub struct Trigger {
+    pub condition: Condition,
+    was_true: Option<bool>,
+}
+
+impl Trigger {
+    pub fn new(condition: Condition) -> Self {
+        Self {
+            condition,
+            was_true: None,
+        }
+    }
+
+    pub fn crosses(variable: Entity, threshold: f64, direction: Direction) -> Self {
+        Self::new(Condition::Crosses {
+            variable,
+            threshold,
+            direction,
+        })
+    }
+
+    pub fn zero_crossing(variable: Entity, direction: Direction) -> Self {
+        Self::crosses(variable, 0., direction)
+    }
+
+    pub fn becomes<T: Lam + 'static>(equation: T) -> Self {
+        Self::new(Condition::Becomes(Arc::new(equation)))
+    }
+}
+
+/// Compare every variable with its value from the last cycle and report the ones that moved.
+pub fn detect_variable_changes(
+    var_query: Query<(Entity, &Variable)>,
+    mut last_values: Local<HashMap<Entity, f64>>,
+    mut events: EventWriter<VariableChanged>,
+) {
+    last_values.retain(|e, _| var_query.get(*e).is_ok());
+    for (entity, var) in var_query.iter() {
+        let new = var.value();
+        if let Some(old) = last_values.insert(entity, new) {
+            if old != new {
+                events.send(VariableChanged { entity, old, new });
+            }
+        }
+    }
+}
+
+/// Fire every trigger whose condition was met this cycle.
+pub fn check_triggers(
+    mut trigger_query: Query<(Entity, &mut Trigger)>,
+    var_query: Query<(Entity, &Variable)>,
+    mut changes: EventReader<VariableChanged>,
+    mut events: EventWriter<Triggered>,
+) {
+    let changes: HashMap<Entity, (f64, f64)> =
+        changes.iter().map(|w| (w.entity, (w.old, w.new))).collect();
+    let context: Context = var_query.iter().map(|(e, v)| (e, v.value())).collect();
+
+    for (entity, mut trigger) in trigger_query.iter_mut() {
+        let trigger = &mut *trigger;
+        let fired = match &trigger.condition {
+            Condition::Crosses {
+                variable,
+                threshold,
+                direction,
+            } => match changes.get(variable) {
+                Some(&(old, new)) => {
+                    let rising = old < *threshold && new >= *threshold;
+                    let falling = old > *threshold && new <= *threshold;
+                    match direction {
+                        Direction::Rising => rising,
+                        Direction::Falling => falling,
+                        Direction::Either => rising || falling,
+                    }
+                }
+                None => false,
+            },
+            Condition::Becomes(equation) => {
+                if !equation.children().iter().all(|w| context.contains_key(w)) {
+                    continue;
+                }
+                let is_true = equation.get(&context) > 0.;
+                let fired = is_true && trigger.was_true == Some(false);
+                trigger.was_true = Some(is_true);
+                fired
+            }
+        };
+        if fired {
+            events.send(Triggered { trigger: entity });
+        }
+    }
+}
@@ -0,0 +1,190 @@
+use std::sync::Arc;
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use super::group::Group;
+use super::lambda::{self, Context, Lam};
+
+/// A plain-data equation tree, mirroring one node of [`lambda`] per variant.
+///
+/// Unlike the generic structs in [`lambda`], an `Expr` can be cloned, compared and
+/// pattern matched. Use [`Lam::to_expr`] to get one from an existing equation and
+/// [`Expr::into_lam`] to turn it back into one.
+///
+/// Variables are entities, which are not the same from one run to the next. To save an
+/// equation, turn it into a [`SavedExpr`] with [`Expr::save`] and serialize that instead.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Expr<V = Entity> {
+    Add(Box<Expr<V>>, Box<Expr<V>>),
+    Sub(Box<Expr<V>>, Box<Expr<V>>),
+    Mul(Box<Expr<V>>, Box<Expr<V>>),
+    Div(Box<Expr<V>>, Box<Expr<V>>),
+    Mod(Box<Expr<V>>, Box<Expr<V>>),
+    Sin(Box<Expr<V>>),
+    Cos(Box<Expr<V>>),
+    Tan(Box<Expr<V>>),
+    Var(V),
+    Num(f64),
+    Sum(Vec<V>),
+}
+
+/// A variable named by its group and name, which stay the same from one run to the next.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct VarKey {
+    pub group: usize,
+    pub name: String,
+}
+
+impl VarKey {
+    pub fn new(group: &Group, name: &Name) -> Self {
+        Self {
+            group: group.0,
+            name: name.to_string(),
+        }
+    }
+}
+
+/// An equation that names its variables by [`VarKey`], ready to be serialized.
+pub type SavedExpr = Expr<VarKey>;
+
+impl<V> Expr<V> {
+    /// The direct sub-expressions of this node.
+    pub fn operands(&self) -> Vec<&Expr<V>> {
+        match self {
+            Expr::Add(a, b)
+            | Expr::Sub(a, b)
+            | Expr::Mul(a, b)
+            | Expr::Div(a, b)
+            | Expr::Mod(a, b) => vec![&**a, &**b],
+            Expr::Sin(a) | Expr::Cos(a) | Expr::Tan(a) => vec![&**a],
+            Expr::Var(_) | Expr::Num(_) | Expr::Sum(_) => Vec::new(),
+        }
+    }
+
+    /// The direct sub-expressions of this node, mutably.
+    pub fn operands_mut(&mut self) -> Vec<&mut Expr<V>> {
+        match self {
+            Expr::Add(a, b)
+            | Expr::Sub(a, b)
+            | Expr::Mul(a, b)
+            | Expr::Div(a, b)
+            | Expr::Mod(a, b) => vec![&mut **a, &mut **b],
+            Expr::Sin(a) | Expr::Cos(a) | Expr::Tan(a) => vec![&mut **a],
+            Expr::Var(_) | Expr::Num(_) | Expr::Sum(_) => Vec::new(),
+        }
+    }
+
+    /// Visit every node of the tree, parents before their operands.
+    pub fn walk(&self, visit: &mut impl FnMut(&Expr<V>)) {
+        visit(self);
+        for operand in self.operands() {
+            operand.walk(visit);
+        }
+    }
+
+    /// Visit every node of the tree mutably, parents before their operands.
+    pub fn walk_mut(&mut self, visit: &mut impl FnMut(&mut Expr<V>)) {
+        visit(self);
+        for operand in self.operands_mut() {
+            operand.walk_mut(visit);
+        }
+    }
+
+    /// The same tree with every variable replaced by `map` of it, or `None` if `map` returns
+    /// `None` for any of them.
+    pub fn map_vars<W>(&self, map: &mut impl FnMut(&V) -> Option<W>) -> Option<Expr<W>> {
+        Some(match self {
+            Expr::Add(a, b) => Expr::Add(Box::new(a.map_vars(map)?), Box::new(b.map_vars(map)?)),
+            Expr::Sub(a, b) => Expr::Sub(Box::new(a.map_vars(map)?), Box::new(b.map_vars(map)?)),
+            Expr::Mul(a, b) => Expr::Mul(Box::new(a.map_vars(map)?), Box::new(b.map_vars(map)?)),
+            Expr::Div(a, b) => Expr::Div(Box::new(a.map_vars(map)?), Box::new(b.map_vars(map)?)),
+            Expr::Mod(a, b) => Expr::Mod(Box::new(a.map_vars(map)?), Box::new(b.map_vars(map)?)),
+            Expr::Sin(a) => Expr::Sin(Box::new(a.map_vars(map)?)),
+            Expr::Cos(a) => Expr::Cos(Box::new(a.map_vars(map)?)),
+            Expr::Tan(a) => Expr::Tan(Box::new(a.map_vars(map)?)),
+            Expr::Var(v) => Expr::Var(map(v)?),
+            Expr::Num(n) => Expr::Num(*n),
+            Expr::Sum(vs) => Expr::Sum(vs.iter().map(&mut *map).collect::<Option<_>>()?),
+        })
+    }
+}
+
+impl Expr {
+    /// A copy to save, naming each variable by its `key`. `None` if a variable has no key.
+    pub fn save(&self, mut key: impl FnMut(Entity) -> Option<VarKey>) -> Option<SavedExpr> {
+        self.map_vars(&mut |w| key(*w))
+    }
+
+    /// Every variable the expression reads from, in tree order.
+    pub fn variables(&self) -> Vec<Entity> {
+        let mut result = Vec::new();
+        self.walk(&mut |w| match w {
+            Expr::Var(e) => result.push(*e),
+            Expr::Sum(es) => result.extend(es.iter().copied()),
+            _ => (),
+        });
+        result
+    }
+
+    /// Rebuild the equation out of the generic structs in [`lambda`].
+    pub fn into_lam(self) -> Arc<dyn Lam> {
+        Arc::from(self.into_boxed())
+    }
+
+    fn into_boxed(self) -> Box<dyn Lam> {
+        match self {
+            Expr::Add(a, b) => Box::new(lambda::Add(a.into_boxed(), b.into_boxed())),
+            Expr::Sub(a, b) => Box::new(lambda::Sub(a.into_boxed(), b.into_boxed())),
+            Expr::Mul(a, b) => Box::new(lambda::Mul(a.into_boxed(), b.into_boxed())),
+            Expr::Div(a, b) => Box::new(lambda::Div(a.into_boxed(), b.into_boxed())),
+            Expr::Mod(a, b) => Box::new(lambda::Mod(a.into_boxed(), b.into_boxed())),
+            Expr::Sin(a) => Box::new(lambda::Sin(a.into_boxed())),
+            Expr::Cos(a) => Box::new(lambda::Cos(a.into_boxed())),
+            Expr::Tan(a) => Box::new(lambda::Tan(a.into_boxed())),
+            Expr::Var(e) => Box::new(lambda::Var(e)),
+            Expr::Num(n) => Box::new(lambda::Num(n)),
+            Expr::Sum(es) => Box::new(lambda::Sum(es)),
+        }
+    }
+}
+
+impl SavedExpr {
+    /// Find the variables of a saved equation again, looking each key up with `entity`. `None`
+    /// if any of them is missing.
+    pub fn resolve(&self, mut entity: impl FnMut(&VarKey) -> Option<Entity>) -> Option<Expr> {
+        self.map_vars(&mut entity)
+    }
+}
+
+impl Lam for Expr {
+    fn get(&self, context: &Context) -> f64 {
+        match self {
+            Expr::Add(a, b) => a.get(context) + b.get(context),
+            Expr::Sub(a, b) => a.get(context) - b.get(context),
+            Expr::Mul(a, b) => a.get(context) * b.get(context),
+            Expr::Div(a, b) => a.get(context) / b.get(context),
+            Expr::Mod(a, b) => a.get(context) % b.get(context),
+            Expr::Sin(a) => a.get(context).sin(),
+            Expr::Cos(a) => a.get(context).cos(),
+            Expr::Tan(a) => a.get(context).tan(),
+            Expr::Var(e) => *context.get(e).unwrap(),
+            Expr::Num(n) => *n,
+            Expr::Sum(es) => es.iter().map(|e| *context.get(e).unwrap()).sum(),
+        }
+    }
+
+    fn children(&self) -> Vec<Entity> {
+        self.variables()
+    }
+
+    fn to_expr(&self) -> Expr {
+        self.clone()
+    }
+}
+
+impl From<Arc<dyn Lam>> for Expr {
+    fn from(lam: Arc<dyn Lam>) -> Self {
+        lam.to_expr()
+    }
+}
@@ -1,15 +1,35 @@
 use bevy::prelude::*;
+use bevy::utils::HashMap;
 
-use super::variable::Variable;
+use super::expr::Expr;
+
+/// The values of every variable an equation may read from, keyed by entity.
+pub type Context = HashMap<Entity, f64>;
 
 pub trait Lam: Send + Sync {
-    fn get(&self, context: &Vec<&mut (Entity, Mut<Variable>)>) -> f64;
+    fn get(&self, context: &Context) -> f64;
     fn children(&self) -> Vec<Entity>;
+    /// Convert the equation into its inspectable [`Expr`] form.
+    fn to_expr(&self) -> Expr;
+}
+
+impl Lam for Box<dyn Lam> {
+    fn get(&self, context: &Context) -> f64 {
+        (**self).get(context)
+    }
+
+    fn children(&self) -> Vec<Entity> {
+        (**self).children()
+    }
+
+    fn to_expr(&self) -> Expr {
+        (**self).to_expr()
+    }
 }
 
 pub struct Add<T: Lam, U: Lam>(pub T, pub U);
 impl<T: Lam, U: Lam> Lam for Add<T, U> {
-    fn get(&self, context: &Vec<&mut (Entity, Mut<Variable>)>) -> f64 {
+    fn get(&self, context: &Context) -> f64 {
         self.0.get(context) + self.1.get(context)
     }
 
@@ -18,11 +38,15 @@ impl<T: Lam, U: Lam> Lam for Add<T, U> {
         temp.append(&mut self.1.children().clone());
         temp
     }
+
+    fn to_expr(&self) -> Expr {
+        Expr::Add(Box::new(self.0.to_expr()), Box::new(self.1.to_expr()))
+    }
 }
 
 pub struct Sub<T: Lam, U: Lam>(pub T, pub U);
 impl<T: Lam, U: Lam> Lam for Sub<T, U> {
-    fn get(&self, context: &Vec<&mut (Entity, Mut<Variable>)>) -> f64 {
+    fn get(&self, context: &Context) -> f64 {
         self.0.get(context) - self.1.get(context)
     }
 
@@ -31,11 +55,15 @@ impl<T: Lam, U: Lam> Lam for Sub<T, U> {
         temp.append(&mut self.1.children().clone());
         temp
     }
+
+    fn to_expr(&self) -> Expr {
+        Expr::Sub(Box::new(self.0.to_expr()), Box::new(self.1.to_expr()))
+    }
 }
 
 pub struct Mul<T: Lam, U: Lam>(pub T, pub U);
 impl<T: Lam, U: Lam> Lam for Mul<T, U> {
-    fn get(&self, context: &Vec<&mut (Entity, Mut<Variable>)>) -> f64 {
+    fn get(&self, context: &Context) -> f64 {
         self.0.get(context) * self.1.get(context)
     }
 
@@ -44,11 +72,15 @@ impl<T: Lam, U: Lam> Lam for Mul<T, U> {
         temp.append(&mut self.1.children().clone());
         temp
     }
+
+    fn to_expr(&self) -> Expr {
+        Expr::Mul(Box::new(self.0.to_expr()), Box::new(self.1.to_expr()))
+    }
 }
 
 pub struct Div<T: Lam, U: Lam>(pub T, pub U);
 impl<T: Lam, U: Lam> Lam for Div<T, U> {
-    fn get(&self, context: &Vec<&mut (Entity, Mut<Variable>)>) -> f64 {
+    fn get(&self, context: &Context) -> f64 {
         self.0.get(context) / self.1.get(context)
     }
 
@@ -57,11 +89,15 @@ impl<T: Lam, U: Lam> Lam for Div<T, U> {
         temp.append(&mut self.1.children().clone());
         temp
     }
+
+    fn to_expr(&self) -> Expr {
+        Expr::Div(Box::new(self.0.to_expr()), Box::new(self.1.to_expr()))
+    }
 }
 
 pub struct Mod<T: Lam, U: Lam>(pub T, pub U);
 impl<T: Lam, U: Lam> Lam for Mod<T, U> {
-    fn get(&self, context: &Vec<&mut (Entity, Mut<Variable>)>) -> f64 {
+    fn get(&self, context: &Context) -> f64 {
         self.0.get(context) % self.1.get(context)
     }
 
@@ -70,90 +106,100 @@ impl<T: Lam, U: Lam> Lam for Mod<T, U> {
         temp.append(&mut self.1.children().clone());
         temp
     }
+
+    fn to_expr(&self) -> Expr {
+        Expr::Mod(Box::new(self.0.to_expr()), Box::new(self.1.to_expr()))
+    }
 }
 
 pub struct Sin<T: Lam>(pub T);
 impl<T: Lam> Lam for Sin<T> {
-    fn get(&self, context: &Vec<&mut (Entity, Mut<Variable>)>) -> f64 {
+    fn get(&self, context: &Context) -> f64 {
         self.0.get(context).sin()
     }
 
     fn children(&self) -> Vec<Entity> {
         self.0.children()
     }
+
+    fn to_expr(&self) -> Expr {
+        Expr::Sin(Box::new(self.0.to_expr()))
+    }
 }
 
 pub struct Cos<T: Lam>(pub T);
 impl<T: Lam> Lam for Cos<T> {
-    fn get(&self, context: &Vec<&mut (Entity, Mut<Variable>)>) -> f64 {
+    fn get(&self, context: &Context) -> f64 {
         self.0.get(context).cos()
     }
 
     fn children(&self) -> Vec<Entity> {
         self.0.children()
     }
+
+    fn to_expr(&self) -> Expr {
+        Expr::Cos(Box::new(self.0.to_expr()))
+    }
 }
 
 pub struct Tan<T: Lam>(pub T);
 impl<T: Lam> Lam for Tan<T> {
-    fn get(&self, context: &Vec<&mut (Entity, Mut<Variable>)>) -> f64 {
+    fn get(&self, context: &Context) -> f64 {
         self.0.get(context).tan()
     }
 
     fn children(&self) -> Vec<Entity> {
         self.0.children()
     }
+
+    fn to_expr(&self) -> Expr {
+        Expr::Tan(Box::new(self.0.to_expr()))
+    }
 }
 
 pub struct Var(pub Entity);
 impl Lam for Var {
-    fn get(&self, context: &Vec<&mut (Entity, Mut<Variable>)>) -> f64 {
-        context
-            .iter()
-            .filter(|w| w.0 == self.0)
-            .next()
-            .unwrap()
-            .1
-            .value()
+    fn get(&self, context: &Context) -> f64 {
+        *context.get(&self.0).unwrap()
     }
 
     fn children(&self) -> Vec<Entity> {
         vec![self.0]
     }
+
+    fn to_expr(&self) -> Expr {
+        Expr::Var(self.0)
+    }
 }
 
 pub struct Num(pub f64);
 impl Lam for Num {
-    fn get(&self, _context: &Vec<&mut (Entity, Mut<Variable>)>) -> f64 {
+    fn get(&self, _context: &Context) -> f64 {
         self.0
     }
 
     fn children(&self) -> Vec<Entity> {
         Vec::new()
     }
+
+    fn to_expr(&self) -> Expr {
+        Expr::Num(self.0)
+    }
 }
 
 pub struct Sum(pub Vec<Entity>);
 impl Lam for Sum {
-    fn get(&self, context: &Vec<&mut (Entity, Mut<Variable>)>) -> f64 {
-        0. + self
-            .0
-            .iter()
-            .map(|e| {
-                context
-                    .iter()
-                    .filter(|w| w.0 == *e)
-                    .next()
-                    .unwrap()
-                    .1
-                    .value()
-            })
-            .sum::<f64>()
+    fn get(&self, context: &Context) -> f64 {
+        0. + self.0.iter().map(|e| *context.get(e).unwrap()).sum::<f64>()
     }
 
     fn children(&self) -> Vec<Entity> {
         self.0.clone()
     }
+
+    fn to_expr(&self) -> Expr {
+        Expr::Sum(self.0.clone())
+    }
 }
 impl Sum {
     pub fn add(&mut self, new_entry: Entity) {
@@ -163,3 +209,161 @@ impl Sum {
         self.0.retain(|&w| w != entity);
     }
 }
+
+impl Lam for f64 {
+    fn get(&self, _context: &Context) -> f64 {
+        *self
+    }
+
+    fn children(&self) -> Vec<Entity> {
+        Vec::new()
+    }
+
+    fn to_expr(&self) -> Expr {
+        Expr::Num(*self)
+    }
+}
+
+pub fn sin<T: Lam>(x: T) -> Sin<T> {
+    Sin(x)
+}
+
+pub fn cos<T: Lam>(x: T) -> Cos<T> {
+    Cos(x)
+}
+
+pub fn tan<T: Lam>(x: T) -> Tan<T> {
+    Tan(x)
+}
+
+/// Lets equation nodes be combined with `+`, `-`, `*`, `/`, `%` and unary `-`,
+/// both with each other and with plain `f64`s.
+macro_rules! impl_ops {
+    ($node: ident $(<$($generic: ident),*>)?) => {
+        impl<$($($generic: Lam,)*)? R: Lam> std::ops::Add<R> for $node$(<$($generic),*>)? {
+            type Output = Add<Self, R>;
+            fn add(self, rhs: R) -> Self::Output {
+                Add(self, rhs)
+            }
+        }
+        impl<$($($generic: Lam,)*)? R: Lam> std::ops::Sub<R> for $node$(<$($generic),*>)? {
+            type Output = Sub<Self, R>;
+            fn sub(self, rhs: R) -> Self::Output {
+                Sub(self, rhs)
+            }
+        }
+        impl<$($($generic: Lam,)*)? R: Lam> std::ops::Mul<R> for $node$(<$($generic),*>)? {
+            type Output = Mul<Self, R>;
+            fn mul(self, rhs: R) -> Self::Output {
+                Mul(self, rhs)
+            }
+        }
+        impl<$($($generic: Lam,)*)? R: Lam> std::ops::Div<R> for $node$(<$($generic),*>)? {
+            type Output = Div<Self, R>;
+            fn div(self, rhs: R) -> Self::Output {
+                Div(self, rhs)
+            }
+        }
+        impl<$($($generic: Lam,)*)? R: Lam> std::ops::Rem<R> for $node$(<$($generic),*>)? {
+            type Output = Mod<Self, R>;
+            fn rem(self, rhs: R) -> Self::Output {
+                Mod(self, rhs)
+            }
+        }
+        impl$(<$($generic: Lam),*>)? std::ops::Neg for $node$(<$($generic),*>)? {
+            type Output = Sub<Num, Self>;
+            fn neg(self) -> Self::Output {
+                Sub(Num(0.), self)
+            }
+        }
+        impl$(<$($generic: Lam),*>)? std::ops::Add<$node$(<$($generic),*>)?> for f64 {
+            type Output = Add<Num, $node$(<$($generic),*>)?>;
+            fn add(self, rhs: $node$(<$($generic),*>)?) -> Self::Output {
+                Add(Num(self), rhs)
+            }
+        }
+        impl$(<$($generic: Lam),*>)? std::ops::Sub<$node$(<$($generic),*>)?> for f64 {
+            type Output = Sub<Num, $node$(<$($generic),*>)?>;
+            fn sub(self, rhs: $node$(<$($generic),*>)?) -> Self::Output {
+                Sub(Num(self), rhs)
+            }
+        }
+        impl$(<$($generic: Lam),*>)? std::ops::Mul<$node$(<$($generic),*>)?> for f64 {
+            type Output = Mul<Num, $node$(<$($generic),*>)?>;
+            fn mul(self, rhs: $node$(<$($generic),*>)?) -> Self::Output {
+                Mul(Num(self), rhs)
+            }
+        }
+        impl$(<$($generic: Lam),*>)? std::ops::Div<$node$(<$($generic),*>)?> for f64 {
+            type Output = Div<Num, $node$(<$($generic),*>)?>;
+            fn div(self, rhs: $node$(<$($generic),*>)?) -> Self::Output {
+                Div(Num(self), rhs)
+            }
+        }
+        impl$(<$($generic: Lam),*>)? std::ops::Rem<$node$(<$($generic),*>)?> for f64 {
+            type Output = Mod<Num, $node$(<$($generic),*>)?>;
+            fn rem(self, rhs: $node$(<$($generic),*>)?) -> Self::Output {
+                Mod(Num(self), rhs)
+            }
+        }
+    };
+}
+
+impl_ops!(Add<T, U>);
+impl_ops!(Sub<T, U>);
+impl_ops!(Mul<T, U>);
+impl_ops!(Div<T, U>);
+impl_ops!(Mod<T, U>);
+impl_ops!(Sin<T>);
+impl_ops!(Cos<T>);
+impl_ops!(Tan<T>);
+impl_ops!(Var);
+impl_ops!(Num);
+impl_ops!(Sum);
+
+/// Build an equation from ordinary arithmetic syntax.
+///
+/// Identifiers are read as variable entities, so every name must be an `Entity` in scope.
+/// Number literals become constants, `sin`, `cos` and `tan` wrap their argument, and any
+/// other Rust expression producing an `f64` can be embedded as a constant with braces.
+///
+/// ```ignore
+/// let theta = expr!((phase + time * freq) % {2. * PI});
+/// let y = expr!(amp * sin(theta) - 200.);
+/// ```
+#[macro_export]
+macro_rules! expr {
+    (@munch [$($out: tt)*]) => {
+        $($out)*
+    };
+    (@munch [$($out: tt)*] sin ($($arg: tt)*) $($rest: tt)*) => {
+        $crate::expr!(@munch [$($out)* $crate::variables::lambda::Sin($crate::expr!($($arg)*))] $($rest)*)
+    };
+    (@munch [$($out: tt)*] cos ($($arg: tt)*) $($rest: tt)*) => {
+        $crate::expr!(@munch [$($out)* $crate::variables::lambda::Cos($crate::expr!($($arg)*))] $($rest)*)
+    };
+    (@munch [$($out: tt)*] tan ($($arg: tt)*) $($rest: tt)*) => {
+        $crate::expr!(@munch [$($out)* $crate::variables::lambda::Tan($crate::expr!($($arg)*))] $($rest)*)
+    };
+    (@munch [$($out: tt)*] ($($inner: tt)*) $($rest: tt)*) => {
+        $crate::expr!(@munch [$($out)* ($crate::expr!($($inner)*))] $($rest)*)
+    };
+    (@munch [$($out: tt)*] {$value: expr} $($rest: tt)*) => {
+        $crate::expr!(@munch [$($out)* $crate::variables::lambda::Num($value)] $($rest)*)
+    };
+    (@munch [$($out: tt)*] $var: ident $($rest: tt)*) => {
+        $crate::expr!(@munch [$($out)* $crate::variables::lambda::Var($var)] $($rest)*)
+    };
+    (@munch [$($out: tt)*] - $($rest: tt)*) => {
+        $crate::expr!(@munch [$($out)* -] $($rest)*)
+    };
+    (@munch [$($out: tt)*] $num: literal $($rest: tt)*) => {
+        $crate::expr!(@munch [$($out)* $crate::variables::lambda::Num(f64::from($num))] $($rest)*)
+    };
+    (@munch [$($out: tt)*] $op: tt $($rest: tt)*) => {
+        $crate::expr!(@munch [$($out)* $op] $($rest)*)
+    };
+    ($($tokens: tt)*) => {
+        $crate::expr!(@munch [] $($tokens)*)
+    };
+}
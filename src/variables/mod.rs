@@ -1,31 +1,87 @@
 //! This crate is a way to have generic and dynamic calculations based on various factors
 //! and bind game entities to the outcome of said calculations.
 use bevy::prelude::*;
+use bevy::tasks::ComputeTaskPool;
+use bevy::time::FixedTimestep;
 
+use self::binding::{store_previous_values, PreviousValues};
+use self::events::{check_triggers, detect_variable_changes, Triggered, VariableChanged};
 pub use self::variable::{Dependent, Independent, Variable};
 
 /// Traits and methods to use Variable and Equation values with other components.
 pub mod binding;
+/// A fluent way to spawn variables along with their metadata.
+pub mod builder;
 /// Plugins for debugging calculations and systems.
 pub mod debug;
+/// Events for changing variables, and triggers that fire when they reach a condition.
+pub mod events;
+/// Plain-data equation trees that can be inspected, compared and serialized.
+pub mod expr;
 /// Used to mark subspaces of data.
 pub mod group;
 /// The package handling data-oriented declaration of dynamic equations.
 pub mod lambda;
+/// Splits the variable dependency graph into independently evaluable subgraphs.
+pub mod schedule;
 /// The core of calculations. Holds equations and values.
 pub mod variable;
 
-/// Adds variable recalculation systems.
-pub struct VariablePlugin;
+/// The stage the simulation runs in. It runs a fixed number of times per second of real time,
+/// however many frames are rendered in between.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, StageLabel)]
+pub struct SimulationStage;
+
+/// The label of the [`SimulationStage`]'s timestep in [`bevy::time::FixedTimesteps`].
+pub const SIMULATION_TIMESTEP: &str = "simulation";
+
+/// Adds the [`SimulationStage`] and the variable recalculation systems that run in it.
+pub struct VariablePlugin {
+    pub steps_per_second: f64,
+}
+
+impl Default for VariablePlugin {
+    fn default() -> Self {
+        Self {
+            steps_per_second: 120.,
+        }
+    }
+}
 
 impl Plugin for VariablePlugin {
     fn build(&self, app: &mut App) {
-        app.add_system_set(
+        app.add_stage_before(
+            CoreStage::Update,
+            SimulationStage,
+            SystemStage::parallel().with_run_criteria(
+                FixedTimestep::steps_per_second(self.steps_per_second)
+                    .with_label(SIMULATION_TIMESTEP),
+            ),
+        )
+        .init_resource::<PreviousValues>()
+        .add_system_to_stage(
+            SimulationStage,
+            store_previous_values
+                .label("store_previous")
+                .before("variable_recalculation"),
+        )
+        .add_system_set_to_stage(
+            SimulationStage,
             SystemSet::new()
                 .label("variable_recalculation")
                 .with_system(devaluate_variables.label("devaluate"))
                 .with_system(evaluate_variables.after("devaluate")),
-        );
+        )
+        .add_system_set_to_stage(
+            SimulationStage,
+            SystemSet::new()
+                .label("variable_events")
+                .after("variable_recalculation")
+                .with_system(detect_variable_changes.label("detect_changes"))
+                .with_system(check_triggers.after("detect_changes")),
+        )
+        .add_event::<VariableChanged>()
+        .add_event::<Triggered>();
     }
 }
 
@@ -37,39 +93,23 @@ pub fn devaluate_variables(mut var_query: Query<&mut Variable>) {
 }
 
 /// Keep evaluating variables until they all have an f64 value for the current cycle.
+///
+/// Each connected component of the dependency graph is evaluated as its own task on the
+/// compute task pool, and the results are written back once every task has finished.
 pub fn evaluate_variables(mut var_query: Query<(Entity, &mut Variable)>) {
-    while var_query.iter().any(|w| !w.1.recalculated()) {
-        let mut vars: Vec<_> = var_query.iter_mut().collect();
-        let (finished, mut unfinished): (_, Vec<_>) =
-            vars.iter_mut().partition(|x| x.1.recalculated());
-        if unfinished
-            .iter_mut()
-            .filter(|w| {
-                let needed = (*w.1).equation().children();
-                needed.iter().all(|v| {
-                    finished
-                        .iter()
-                        .map(|u| u.0)
-                        .collect::<Vec<Entity>>()
-                        .contains(&v)
-                })
-            })
-            .count()
-            == 0
-        {
-            return;
+    let variables: Vec<_> = var_query.iter().map(|(e, v)| (e, v.clone())).collect();
+    let subgraphs = schedule::partition(variables);
+
+    let results = ComputeTaskPool::get().scope(|scope| {
+        for subgraph in subgraphs.into_iter().filter(|w| w.is_stale()) {
+            scope.spawn(async move { subgraph.evaluate() });
         }
-        for var in unfinished.iter_mut().filter(|w| {
-            let needed = (*w.1).equation().children();
-            needed.iter().all(|v| {
-                finished
-                    .iter()
-                    .map(|u| u.0)
-                    .collect::<Vec<Entity>>()
-                    .contains(&v)
-            })
-        }) {
-            var.1.calculate(&finished);
+    });
+
+    for (entity, value) in results.into_iter().flatten() {
+        if let Ok((_, mut var)) = var_query.get_mut(entity) {
+            var.set_value(value);
+            var.set_recalculated(true);
         }
     }
 }
@@ -0,0 +1,110 @@
+use bevy::prelude::*;
+use bevy::utils::HashMap;
+
+use super::{lambda::Context, variable::Variable};
+
+/// A set of variables that shares no dependencies with any other subgraph.
+pub struct Subgraph {
+    variables: Vec<(Entity, Variable)>,
+}
+
+impl Subgraph {
+    /// Whether any variable in the subgraph still needs evaluating this cycle.
+    pub fn is_stale(&self) -> bool {
+        self.variables.iter().any(|w| !w.1.recalculated())
+    }
+
+    /// Evaluate every stale variable whose dependencies can be resolved, returning the new values.
+    ///
+    /// Variables are evaluated in dependency order, using a count per variable of the
+    /// dependencies it is still waiting on (Kahn's algorithm). Each one only reads values that
+    /// are already final, which gives the same results as evaluating the whole graph serially.
+    pub fn evaluate(self) -> Vec<(Entity, f64)> {
+        let (finished, mut unfinished): (Vec<_>, Vec<_>) =
+            self.variables.into_iter().partition(|w| w.1.recalculated());
+        let mut context: Context = finished.iter().map(|w| (w.0, w.1.value())).collect();
+        let index: HashMap<Entity, usize> = unfinished
+            .iter()
+            .enumerate()
+            .map(|(i, w)| (w.0, i))
+            .collect();
+
+        // Dependencies that are neither known nor in the subgraph are never resolved, so the
+        // variables reading them are never evaluated.
+        let mut waiting = vec![0_usize; unfinished.len()];
+        let mut dependents = vec![Vec::new(); unfinished.len()];
+        for (i, (_, var)) in unfinished.iter().enumerate() {
+            let mut children = var.children();
+            children.sort();
+            children.dedup();
+            for child in children.into_iter().filter(|w| !context.contains_key(w)) {
+                waiting[i] += 1;
+                if let Some(&j) = index.get(&child) {
+                    dependents[j].push(i);
+                }
+            }
+        }
+
+        let mut ready: Vec<usize> = (0..unfinished.len()).filter(|&i| waiting[i] == 0).collect();
+        let mut results = Vec::with_capacity(unfinished.len());
+        while let Some(i) = ready.pop() {
+            let (entity, var) = &mut unfinished[i];
+            var.calculate(&context);
+            context.insert(*entity, var.value());
+            results.push((*entity, var.value()));
+            for &j in dependents[i].iter() {
+                waiting[j] -= 1;
+                if waiting[j] == 0 {
+                    ready.push(j);
+                }
+            }
+        }
+        results
+    }
+}
+
+/// Split variables into the connected components of their dependency graph.
+pub fn partition(variables: Vec<(Entity, Variable)>) -> Vec<Subgraph> {
+    let index: HashMap<Entity, usize> = variables
+        .iter()
+        .enumerate()
+        .map(|(i, w)| (w.0, i))
+        .collect();
+    let mut parents: Vec<usize> = (0..variables.len()).collect();
+
+    for (i, (_, var)) in variables.iter().enumerate() {
+        for child in var.children() {
+            if let Some(&j) = index.get(&child) {
+                let (a, b) = (root(&mut parents, i), root(&mut parents, j));
+                parents[a] = b;
+            }
+        }
+    }
+
+    let mut subgraphs: HashMap<usize, Subgraph> = HashMap::default();
+    for (i, var) in variables.into_iter().enumerate() {
+        subgraphs
+            .entry(root(&mut parents, i))
+            .or_insert_with(|| Subgraph {
+                variables: Vec::new(),
+            })
+            .variables
+            .push(var);
+    }
+    subgraphs.into_values().collect()
+}
+
+/// Find the representative of `i`'s set, compressing the path along the way.
+fn root(parents: &mut [usize], i: usize) -> usize {
+    let mut r = i;
+    while parents[r] != r {
+        r = parents[r];
+    }
+    let mut i = i;
+    while parents[i] != r {
+        let next = parents[i];
+        parents[i] = r;
+        i = next;
+    }
+    r
+}
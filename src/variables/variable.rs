@@ -1,9 +1,10 @@
 use bevy::prelude::*;
+use std::ops::RangeInclusive;
 use std::sync::Arc;
 
 use super::{
-    group::Group,
-    lambda::{Lam, Num},
+    expr::Expr,
+    lambda::{Context, Lam, Num},
 };
 
 #[derive(Clone, Component)]
@@ -80,6 +81,11 @@ impl Variable {
         }
     }
 
+    /// The equation in its inspectable form. Independent variables give their constant value.
+    pub fn expr(&self) -> Expr {
+        self.equation().to_expr()
+    }
+
     pub fn equation_mut(&mut self) -> Option<&mut Arc<dyn Lam>> {
         match self {
             Variable::Independent { value: _ } => None,
@@ -102,48 +108,23 @@ impl Variable {
         }
     }
 
-    pub fn calculate(&mut self, context: &Vec<&mut (Entity, Mut<Variable>)>) {
+    pub fn calculate(&mut self, context: &Context) {
         self.set_recalculated(true);
         self.set_value(self.equation().get(context));
     }
 }
 
-#[derive(Bundle)]
-pub struct VariableBundle {
-    variable: Variable,
-    name: Name,
-}
+/// The range of values a variable is meant to take, used by anything that edits it.
+#[derive(Clone, Component)]
+pub struct ValueRange(pub RangeInclusive<f64>);
 
-pub fn dependent<T: Lam + 'static>(
-    commands: &mut Commands,
-    group: &Group,
-    name: &'static str,
-    equation: T,
-) -> Entity {
-    commands
-        .spawn()
-        .insert(Name::new(name))
-        .insert(Variable::Dependent {
-            value: 0.,
-            recalculated: false,
-            equation: Arc::new(equation),
-        })
-        .insert(Dependent)
-        .insert(group.clone())
-        .id()
-}
+/// The period of a variable that wraps around, such as an angle kept between 0 and 2π. Shapes
+/// bound to it jump straight across the wrap instead of sweeping back through every value.
+#[derive(Clone, Copy, Component)]
+pub struct Modulus(pub f64);
 
-pub fn independent(
-    commands: &mut Commands,
-    group: &Group,
-    name: &'static str,
-    value: f64,
-) -> Entity {
-    commands
-        .spawn()
-        .insert(Name::new(name))
-        .insert(Variable::Independent { value })
-        .insert(Independent)
-        .insert(group.clone())
-        .id()
+#[derive(Bundle)]
+pub struct VariableBundle {
+    pub variable: Variable,
+    pub name: Name,
 }
@@ -0,0 +1,35 @@
+//! Filling in the templates of [`BoundText`] from the values bound to it.
+
+use bevy::prelude::*;
+use fourier::drawing::boundtext::BoundText;
+use fourier::variables::binding::Bound;
+
+/// A text reading `template`, with `amp` and `freq` bound to the given values.
+fn text(template: &str, amp: f64, freq: f64) -> BoundText {
+    let mut text = BoundText::new(template)
+        .var("amp", Entity::from_raw(1))
+        .var("freq", Entity::from_raw(2));
+    text.set_bindings(vec![amp, freq]);
+    text
+}
+
+#[test]
+fn doubled_braces_are_written_once() {
+    assert_eq!(text("{{amp}}", 2.5, 1.).render(), "{amp}");
+    assert_eq!(text("{{{amp}}}", 2.5, 1.).render(), "{2.5}");
+    assert_eq!(text("a }} b {{", 2.5, 1.).render(), "a } b {");
+}
+
+#[test]
+fn unknown_names_are_left_as_written() {
+    assert_eq!(text("{phase} {amp}", 2.5, 1.).render(), "{phase} 2.5");
+    assert_eq!(text("{phase:.2}", 2.5, 1.).render(), "{phase:.2}");
+    assert_eq!(text("open { brace", 2.5, 1.).render(), "open { brace");
+}
+
+#[test]
+fn precision_rounds_the_value() {
+    assert_eq!(text("A = {amp:.1}", 2.46, 1.).render(), "A = 2.5");
+    assert_eq!(text("{freq:.3} Hz", 2.5, 0.5).render(), "0.500 Hz");
+    assert_eq!(text("{amp:.0}/{freq}", 2.7, 0.25).render(), "3/0.25");
+}
@@ -0,0 +1,83 @@
+//! Round trips between equations, their [`Expr`] form and the saved form of that.
+
+use std::f64::consts::PI;
+
+use bevy::prelude::*;
+use fourier::expr;
+use fourier::variables::expr::{Expr, SavedExpr, VarKey};
+use fourier::variables::lambda::{Context, Lam, Sum};
+
+/// Contexts giving `vars` a spread of values, including ones that make `%` wrap.
+fn contexts(vars: &[Entity]) -> Vec<Context> {
+    (0..20)
+        .map(|step| {
+            vars.iter()
+                .enumerate()
+                .map(|(index, var)| (*var, (step as f64 * 0.7 - 3.) * (index as f64 + 1.)))
+                .collect()
+        })
+        .collect()
+}
+
+#[test]
+fn lam_to_expr_to_lam_evaluates_the_same() {
+    let (phase, time, freq, amp) = (
+        Entity::from_raw(1),
+        Entity::from_raw(2),
+        Entity::from_raw(3),
+        Entity::from_raw(4),
+    );
+    let theta = expr!((phase + time * freq) % { 2. * PI });
+    let wave = expr!(amp * sin(phase) - cos(time) / 2. + tan(freq * 0.1));
+    let sum = Sum(vec![phase, time, amp]);
+    let lams: Vec<&dyn Lam> = vec![&theta, &wave, &sum];
+
+    for lam in lams {
+        let expr = lam.to_expr();
+        let rebuilt = expr.clone().into_lam();
+        assert_eq!(rebuilt.to_expr(), expr);
+        for context in contexts(&[phase, time, freq, amp]) {
+            assert_eq!(lam.get(&context), rebuilt.get(&context));
+        }
+    }
+}
+
+#[test]
+fn saved_expr_resolves_to_new_entities() {
+    let (phase, time, freq, amp) = (
+        Entity::from_raw(1),
+        Entity::from_raw(2),
+        Entity::from_raw(3),
+        Entity::from_raw(4),
+    );
+    let old = [phase, time, freq, amp];
+    // The same variables in a later run, spawned in a different order.
+    let new = [
+        Entity::from_raw(40),
+        Entity::from_raw(30),
+        Entity::from_raw(20),
+        Entity::from_raw(10),
+    ];
+    let key = |entity: Entity| {
+        old.iter().position(|w| *w == entity).map(|index| VarKey {
+            group: 1,
+            name: format!("var{}", index),
+        })
+    };
+    let find = |key: &VarKey| {
+        let index: usize = key.name.trim_start_matches("var").parse().ok()?;
+        new.get(index).copied()
+    };
+    let lam = expr!(amp * sin((phase + time * freq) % { 2. * PI }) + amp);
+    let expr = lam.to_expr();
+
+    let text = serde_json::to_string(&expr.save(key).unwrap()).unwrap();
+    let saved: SavedExpr = serde_json::from_str(&text).unwrap();
+    let loaded: Expr = saved.resolve(find).unwrap();
+
+    assert_eq!(loaded.variables().len(), expr.variables().len());
+    for (old_context, new_context) in contexts(&old).into_iter().zip(contexts(&new)) {
+        assert_eq!(lam.get(&old_context), loaded.get(&new_context));
+    }
+    assert!(saved.resolve(|_| None).is_none());
+}
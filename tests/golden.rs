@@ -0,0 +1,120 @@
+//! Runs each page without a window and compares its key variables with the traces in
+//! `tests/golden`. Run with `UPDATE_GOLDEN=1` to write the traces after an intended change.
+
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+
+use bevy::prelude::*;
+use fourier::headless::{add_rows, headless_app, page_columns, write_trace};
+use fourier::variables::Variable;
+use fourier::Page;
+
+const STEPS: usize = 240;
+const SEED: u64 = 42;
+
+fn trace(page: Page, rows: usize, names: &[&str]) -> String {
+    trace_with(page, rows, names, |_| {})
+}
+
+/// Trace `page` after letting `setup` change the app once the page has been set up.
+fn trace_with(page: Page, rows: usize, names: &[&str], setup: impl FnOnce(&mut App)) -> String {
+    let mut app = headless_app(page, SEED);
+    add_rows(&mut app, rows);
+    app.update();
+    setup(&mut app);
+
+    let names: Vec<String> = names.iter().map(|w| w.to_string()).collect();
+    let columns = page_columns(&mut app, page, &names);
+    let mut out = Vec::new();
+    write_trace(&mut app, page, &columns, STEPS, &mut out).unwrap();
+    String::from_utf8(out).unwrap()
+}
+
+fn check(golden: &str, actual: &str) {
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/golden")
+        .join(format!("{}.csv", golden));
+    if env::var_os("UPDATE_GOLDEN").is_some() {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, actual).unwrap();
+        eprintln!("Wrote {}", path.display());
+        return;
+    }
+
+    let expected = fs::read_to_string(&path).unwrap_or_else(|_| {
+        panic!(
+            "{} is missing: run with UPDATE_GOLDEN=1 to write it",
+            path.display()
+        )
+    });
+    let mut expected_lines = expected.lines();
+    let mut actual_lines = actual.lines();
+    assert_eq!(
+        expected_lines.next(),
+        actual_lines.next(),
+        "{}: header",
+        golden
+    );
+    assert_eq!(
+        expected.lines().count(),
+        actual.lines().count(),
+        "{}: number of steps",
+        golden
+    );
+    for (expected, actual) in expected_lines.zip(actual_lines) {
+        for (e, a) in expected.split(',').zip(actual.split(',')) {
+            let (e, a): (f64, f64) = (e.parse().unwrap(), a.parse().unwrap());
+            assert!(
+                (e - a).abs() <= 1e-9 * e.abs().max(1.),
+                "{}: expected {} but got {} in\n{}",
+                golden,
+                e,
+                a,
+                actual
+            );
+        }
+    }
+}
+
+#[test]
+fn simple_sine() {
+    check("simple", &trace(Page::Simple, 0, &["sin(theta)"]));
+}
+
+#[test]
+fn combination_sum() {
+    check("combination", &trace(Page::Combination, 0, &["sum"]));
+}
+
+#[test]
+fn game_both_groups() {
+    check("game", &trace(Page::Game, 0, &["sin(theta)"]));
+}
+
+/// The rows start with values from the seeded RNG. They are set to known values before tracing,
+/// so the trace does not depend on how the RNG turns its seed into numbers.
+#[test]
+fn fourier_sum_of_rows() {
+    let rows = [(1., 20., 0.), (3., 10., 0.5), (5., 6., 1.)];
+    let set_rows = |app: &mut App| {
+        let names: Vec<String> = ["freq", "amp", "phase"].map(String::from).to_vec();
+        for (name, entity) in page_columns(app, Page::Fourier, &names) {
+            let (var, group) = name.trim_end_matches(']').split_once('[').unwrap();
+            let (freq, amp, phase) = rows[group.parse::<usize>().unwrap() - 8];
+            let value = match var {
+                "freq" => freq,
+                "amp" => amp,
+                _ => phase,
+            };
+            app.world
+                .get_mut::<Variable>(entity)
+                .unwrap()
+                .set_value(value);
+        }
+    };
+    check(
+        "fourier",
+        &trace_with(Page::Fourier, rows.len(), &["sum"], set_rows),
+    );
+}
@@ -0,0 +1,100 @@
+//! Checks that evaluating the variable graph on the task pool, one task per subgraph, gives the
+//! same values as working through it one variable at a time.
+
+use bevy::prelude::*;
+use bevy::tasks::{ComputeTaskPool, TaskPool};
+use fourier::expr;
+use fourier::variables::evaluate_variables;
+use fourier::variables::lambda::{Context, Sum, Var};
+use fourier::variables::Variable;
+
+/// Spawn a few independent subgraphs: layers of variables each reading from the layer before,
+/// a sum over several inputs, and a chain that reuses its own earlier links. Returns the inputs
+/// and the variables calculated from them.
+fn spawn_graph(world: &mut World) -> (Vec<Entity>, Vec<Entity>) {
+    let inputs: Vec<Entity> = (0..8)
+        .map(|i| {
+            Var::independent(format!("input{}", i))
+                .value(i as f64 * 1.3 + 0.5)
+                .spawn(world)
+        })
+        .collect();
+    let mut dependents = Vec::new();
+
+    let mut layer = inputs[..4].to_vec();
+    for depth in 0..6 {
+        layer = (0..layer.len())
+            .map(|k| {
+                let (x, y) = (layer[k], layer[(k * 3 + 1) % layer.len()]);
+                let variable = Var::dependent(
+                    format!("layer{}[{}]", depth, k),
+                    expr!(x * 0.5 + sin(y) - { depth as f64 }),
+                )
+                .spawn(world);
+                dependents.push(variable);
+                variable
+            })
+            .collect();
+    }
+
+    let sum = Var::dependent("sum", Sum(inputs[4..7].to_vec())).spawn(world);
+    dependents.push(sum);
+
+    let (a, b) = (inputs[7], sum);
+    let mut chain = vec![Var::dependent("chain0", expr!(a * b)).spawn(world)];
+    for i in 1..10 {
+        let (last, first) = (chain[i - 1], chain[0]);
+        chain.push(
+            Var::dependent(format!("chain{}", i), expr!(cos(last) + first % 3.)).spawn(world),
+        );
+    }
+    dependents.extend(chain);
+    (inputs, dependents)
+}
+
+/// Evaluate `dependents` one at a time, each as soon as everything it reads has a value.
+fn evaluate_serially(world: &World, inputs: &[Entity], dependents: &[Entity]) -> Context {
+    let mut context: Context = inputs
+        .iter()
+        .map(|w| (*w, world.get::<Variable>(*w).unwrap().value()))
+        .collect();
+    loop {
+        let next = dependents.iter().find(|w| {
+            !context.contains_key(*w)
+                && world
+                    .get::<Variable>(**w)
+                    .unwrap()
+                    .children()
+                    .iter()
+                    .all(|c| context.contains_key(c))
+        });
+        match next {
+            Some(&entity) => {
+                let value = world
+                    .get::<Variable>(entity)
+                    .unwrap()
+                    .equation()
+                    .get(&context);
+                context.insert(entity, value);
+            }
+            None => return context,
+        }
+    }
+}
+
+#[test]
+fn parallel_evaluation_matches_serial() {
+    ComputeTaskPool::init(TaskPool::default);
+    let mut world = World::new();
+    let (inputs, dependents) = spawn_graph(&mut world);
+    let expected = evaluate_serially(&world, &inputs, &dependents);
+
+    let mut stage = SystemStage::single(evaluate_variables);
+    stage.run(&mut world);
+
+    for entity in dependents {
+        let var = world.get::<Variable>(entity).unwrap();
+        assert!(var.recalculated());
+        assert_eq!(var.value(), expected[&entity]);
+    }
+}
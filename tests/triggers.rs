@@ -0,0 +1,133 @@
+//! Drives a variable back and forth across a threshold and checks that each [`Trigger`] fires
+//! once for every crossing it is watching for.
+
+use bevy::ecs::event::{Events, ManualEventReader};
+use bevy::prelude::*;
+use fourier::expr;
+use fourier::variables::events::{
+    check_triggers, detect_variable_changes, Direction, Trigger, Triggered, VariableChanged,
+};
+use fourier::variables::lambda::Var;
+use fourier::variables::Variable;
+
+/// A world with the event systems and a single variable starting at zero.
+fn setup() -> (World, SystemStage, Entity) {
+    let mut world = World::new();
+    world.init_resource::<Events<VariableChanged>>();
+    world.init_resource::<Events<Triggered>>();
+    let x = Var::independent("x").value(0.).spawn(&mut world);
+    let stage = SystemStage::single_threaded()
+        .with_system(detect_variable_changes.label("detect_changes"))
+        .with_system(check_triggers.after("detect_changes"));
+    (world, stage, x)
+}
+
+/// Set `x` to each of `values` in turn, running one cycle for each, and list the triggers that
+/// fired on every cycle.
+fn drive(
+    world: &mut World,
+    stage: &mut SystemStage,
+    x: Entity,
+    values: &[f64],
+) -> Vec<Vec<Entity>> {
+    let mut reader = ManualEventReader::<Triggered>::default();
+    // The first cycle only records the starting value.
+    stage.run(world);
+    reader.iter(world.resource::<Events<Triggered>>()).count();
+    values
+        .iter()
+        .map(|value| {
+            world.get_mut::<Variable>(x).unwrap().set_value(*value);
+            stage.run(world);
+            reader
+                .iter(world.resource::<Events<Triggered>>())
+                .map(|w| w.trigger)
+                .collect()
+        })
+        .collect()
+}
+
+#[test]
+fn crossings_fire_once_in_their_direction() {
+    let (mut world, mut stage, x) = setup();
+    let rising = world
+        .spawn()
+        .insert(Trigger::crosses(x, 1., Direction::Rising))
+        .id();
+    let falling = world
+        .spawn()
+        .insert(Trigger::crosses(x, 1., Direction::Falling))
+        .id();
+    let either = world
+        .spawn()
+        .insert(Trigger::crosses(x, 1., Direction::Either))
+        .id();
+
+    let fired = drive(
+        &mut world,
+        &mut stage,
+        x,
+        &[0.5, 1.5, 2., 1.5, 0.5, 0.5, 3., -1.],
+    );
+    assert_eq!(
+        fired,
+        vec![
+            vec![],
+            vec![rising, either],
+            vec![],
+            vec![],
+            vec![falling, either],
+            vec![],
+            vec![rising, either],
+            vec![falling, either],
+        ]
+    );
+}
+
+#[test]
+fn reaching_the_threshold_counts_as_crossing_it() {
+    let (mut world, mut stage, x) = setup();
+    let rising = world
+        .spawn()
+        .insert(Trigger::zero_crossing(x, Direction::Rising))
+        .id();
+    let falling = world
+        .spawn()
+        .insert(Trigger::zero_crossing(x, Direction::Falling))
+        .id();
+
+    let fired = drive(&mut world, &mut stage, x, &[-1., 0., 1., 0., -1.]);
+    assert_eq!(
+        fired,
+        vec![vec![], vec![rising], vec![], vec![falling], vec![]]
+    );
+}
+
+#[test]
+fn becomes_fires_each_time_the_equation_turns_positive() {
+    let (mut world, mut stage, x) = setup();
+    let above_one = world.spawn().insert(Trigger::becomes(expr!(x - 1.))).id();
+
+    let fired = drive(&mut world, &mut stage, x, &[2., 3., 0.5, 1., 1.5, 4.]);
+    assert_eq!(
+        fired,
+        vec![
+            vec![above_one],
+            vec![],
+            vec![],
+            vec![],
+            vec![above_one],
+            vec![]
+        ]
+    );
+}
+
+#[test]
+fn becomes_does_not_fire_for_an_equation_already_positive() {
+    let (mut world, mut stage, x) = setup();
+    world.get_mut::<Variable>(x).unwrap().set_value(2.);
+    let above_one = world.spawn().insert(Trigger::becomes(expr!(x - 1.))).id();
+
+    let fired = drive(&mut world, &mut stage, x, &[3., 0., 2.]);
+    assert_eq!(fired, vec![vec![], vec![], vec![above_one]]);
+}